@@ -0,0 +1,47 @@
+//! Times the slow parts of the tracker on a synthetic marathon with a lot of entries.
+//!
+//! Run with `cargo bench --features synthetic`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use geoguessr_tracker::marathon_log::MarathonLog;
+use geoguessr_tracker::synthetic::{generate_log, SyntheticConfig};
+use geoguessr_tracker::utils::timekeeping::TWENTY_FOUR_HOURS_IN_SECS;
+
+fn time<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let res = black_box(f());
+    println!("{name}: {:?}", start.elapsed());
+    res
+}
+
+fn main() {
+    // a round every 2 seconds on average over three days, for 100k+ entries
+    let config = SyntheticConfig {
+        duration_secs: 3 * TWENTY_FOUR_HOURS_IN_SECS,
+        mean_round_secs: 2.0,
+        round_secs_spread: 0.5,
+        break_rate: 0.0,
+        ..SyntheticConfig::default()
+    };
+    let marathon_log = time("generate", || generate_log(&config, 0));
+    let entry_count = marathon_log.entries().count();
+    println!("entries: {entry_count}");
+    assert!(entry_count >= 100_000);
+
+    time("add up count", || marathon_log.add_up_count());
+    time("1000 snapshots", || {
+        for i in 0..1000 {
+            black_box(marathon_log.snapshot_at(config.duration_secs / 1000 * i));
+        }
+    });
+
+    let path = std::env::temp_dir().join("geoguessr_tracker_bench.ron");
+    let path = path.to_str().unwrap();
+    let _ = std::fs::remove_file(path);
+    time("save", || marathon_log.save_to_path(path).unwrap());
+    let mut loaded = MarathonLog::new(config.duration_secs);
+    time("load", || loaded.load_from_file(path).unwrap());
+    std::fs::remove_file(path).unwrap();
+}
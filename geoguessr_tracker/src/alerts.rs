@@ -0,0 +1,330 @@
+//! Alerts for milestones and warnings during a marathon, so the runner doesn't have to keep
+//! reading the numbers. The tracker shows them as flashes and can play a tone for each.
+
+use std::fs;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::error::SoundError;
+use crate::i18n::{fmt_int, tr, trf};
+use crate::marathon_log::Snapshot;
+use crate::scoring::ScoringRule;
+use crate::splits::HOUR_SECS;
+use crate::utils::time_counter;
+
+/// The minutes left at which the final hour countdown alerts.
+pub const FINAL_HOUR_MARKS_MINS: [u32; 6] = [60, 30, 15, 10, 5, 1];
+
+const SAMPLE_RATE: u32 = 22_050;
+const BEEP_MS: u32 = 150;
+const GAP_MS: u32 = 100;
+
+/// Which alerts are on. Saved with the rest of the settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertSettings {
+    /// Alert every time the count reaches a multiple of this, 0 for never.
+    pub every_nth_hit: u32,
+    pub hourly: bool,
+    /// Alert when the estimated pace drops below the run's target.
+    pub behind_target: bool,
+    /// Alert when there's been no hit for this many minutes, 0 for never.
+    pub drought_mins: u32,
+    pub final_hour: bool,
+    /// Whether alerts also play a tone.
+    pub sound: bool,
+}
+
+impl Default for AlertSettings {
+    fn default() -> Self {
+        AlertSettings {
+            every_nth_hit: 10,
+            hourly: true,
+            behind_target: true,
+            drought_mins: 15,
+            final_hour: true,
+            sound: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alert {
+    Milestone { count: u32 },
+    Hourly { hour: u32, count: u32 },
+    BehindTarget { pace: u32, target: u32 },
+    Drought { secs: u32 },
+    FinalHour { mins_left: u32 },
+}
+
+impl Alert {
+    /// What the flash says in the current language, with `hit_name` and `count_label` from the
+    /// scoring rule.
+    pub fn message(&self, hit_name: &str, count_label: &str) -> String {
+        match self {
+            Alert::Milestone { count } => trf("{0}: {1}!", &[&count_label, &fmt_int(*count)]),
+            Alert::Hourly { hour, count } => trf(
+                "Hour {0} done: {1} {2}",
+                &[hour, &count_label, &fmt_int(*count)],
+            ),
+            Alert::BehindTarget { pace, target } => trf(
+                "Behind target: on pace for {0} of {1}",
+                &[&fmt_int(*pace), &fmt_int(*target)],
+            ),
+            Alert::Drought { secs } => trf("No {0} for {1}", &[&hit_name, &time_counter(*secs)]),
+            Alert::FinalHour { mins_left: 1 } => tr("1 minute left"),
+            Alert::FinalHour { mins_left } => trf("{0} minutes left", &[mins_left]),
+        }
+    }
+
+    /// Whether the alert is a warning, rather than good news or a check-in.
+    pub fn is_warning(&self) -> bool {
+        matches!(self, Alert::BehindTarget { .. } | Alert::Drought { .. })
+    }
+
+    /// The pitch and number of beeps of the alert's tone.
+    fn tone(&self) -> (f32, u32) {
+        match self {
+            Alert::Milestone { .. } => (880.0, 2),
+            Alert::Hourly { .. } => (660.0, 1),
+            Alert::BehindTarget { .. } => (330.0, 2),
+            Alert::Drought { .. } => (330.0, 3),
+            Alert::FinalHour { .. } => (990.0, 3),
+        }
+    }
+}
+
+/// Keeps track of what was already alerted, so each alert fires once when its condition starts.
+#[derive(Debug, Clone, Default)]
+pub struct AlertTracker {
+    last: Option<Snapshot>,
+    behind_target: bool,
+    drought_alerted: bool,
+}
+
+impl AlertTracker {
+    /// The alerts that fire going from the last snapshot checked to `snapshot`. The first check
+    /// only takes note of where the marathon is, so a loaded log doesn't set everything off.
+    pub fn check(
+        &mut self,
+        settings: &AlertSettings,
+        snapshot: &Snapshot,
+        target: Option<u32>,
+    ) -> Vec<Alert> {
+        let Some(last) = self.last.replace(*snapshot) else {
+            self.behind_target = is_behind(snapshot, target);
+            self.drought_alerted = is_drought(settings, snapshot);
+            return Vec::new();
+        };
+        let mut alerts = Vec::new();
+
+        // milestones of total points would go off on every guess
+        let n = settings.every_nth_hit;
+        let counts_hits = snapshot.scoring_rule != ScoringRule::TotalPoints;
+        if n > 0 && counts_hits && snapshot.count / n > last.count / n {
+            alerts.push(Alert::Milestone {
+                count: snapshot.count / n * n,
+            });
+        }
+        let hour = snapshot.elapsed_secs / HOUR_SECS;
+        if settings.hourly && hour > last.elapsed_secs / HOUR_SECS && !is_end(snapshot) {
+            alerts.push(Alert::Hourly {
+                hour,
+                count: snapshot.count,
+            });
+        }
+
+        let behind_target = is_behind(snapshot, target);
+        if settings.behind_target && behind_target && !self.behind_target {
+            if let (Some(pace), Some(target)) = (snapshot.estimated_pace, target) {
+                alerts.push(Alert::BehindTarget { pace, target });
+            }
+        }
+        self.behind_target = behind_target;
+
+        let drought = is_drought(settings, snapshot);
+        if drought && !self.drought_alerted {
+            alerts.push(Alert::Drought {
+                secs: snapshot.time_since_last_hit.unwrap_or(0),
+            });
+        }
+        self.drought_alerted = drought;
+
+        if settings.final_hour {
+            let (left, last_left) = (snapshot.time_left_secs(), last.time_left_secs());
+            let mark = FINAL_HOUR_MARKS_MINS
+                .into_iter()
+                .filter(|mins| last_left > mins * 60 && left <= mins * 60)
+                .min();
+            if let (Some(mins_left), false) = (mark, is_end(snapshot)) {
+                alerts.push(Alert::FinalHour { mins_left });
+            }
+        }
+        alerts
+    }
+}
+
+fn is_end(snapshot: &Snapshot) -> bool {
+    snapshot.time_left_secs() == 0
+}
+
+fn is_behind(snapshot: &Snapshot, target: Option<u32>) -> bool {
+    matches!((snapshot.estimated_pace, target), (Some(pace), Some(target)) if pace < target)
+}
+
+fn is_drought(settings: &AlertSettings, snapshot: &Snapshot) -> bool {
+    settings.drought_mins > 0
+        && snapshot
+            .time_since_last_hit
+            .is_some_and(|secs| secs >= settings.drought_mins * 60)
+}
+
+/// A mono 16 bit WAV file of `beeps` short beeps at `freq_hz`.
+pub fn tone_wav(freq_hz: f32, beeps: u32) -> Vec<u8> {
+    let beep_samples = SAMPLE_RATE * BEEP_MS / 1000;
+    let gap_samples = SAMPLE_RATE * GAP_MS / 1000;
+    let mut samples = Vec::new();
+    for _ in 0..beeps {
+        for i in 0..beep_samples {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            // fades in and out, so the beep doesn't click
+            let edge = (i.min(beep_samples - i) as f32 / 200.0).min(1.0);
+            let wave = (t * freq_hz * std::f32::consts::TAU).sin();
+            samples.push((wave * edge * 0.4 * f32::from(i16::MAX)) as i16);
+        }
+        samples.extend(std::iter::repeat_n(0, gap_samples as usize));
+    }
+
+    let data_len = samples.len() as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+/// Plays the alert's tone with the platform's sound player, without waiting for it to finish.
+pub fn play_alert_sound(alert: &Alert) -> Result<(), SoundError> {
+    let (freq_hz, beeps) = alert.tone();
+    let path = std::env::temp_dir().join(format!("geoguessr_tracker_{freq_hz:.0}_{beeps}.wav"));
+    let path_txt = path.to_string_lossy().into_owned();
+    if !path.exists() {
+        fs::write(&path, tone_wav(freq_hz, beeps)).map_err(|source| SoundError::WriteTone {
+            path: path.clone(),
+            source,
+        })?;
+    }
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("powershell");
+        command.args([
+            "-NoProfile",
+            "-Command",
+            &format!("(New-Object Media.SoundPlayer '{path_txt}').PlaySync()"),
+        ]);
+        command
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("afplay");
+        command.arg(&path);
+        command
+    } else {
+        let mut command = Command::new("aplay");
+        command.arg("-q").arg(&path);
+        command
+    };
+    let mut child = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|source| SoundError::StartPlayer {
+            player: command.get_program().to_string_lossy().into_owned(),
+            source,
+        })?;
+    thread::spawn(move || {
+        if let Err(err) = child.wait() {
+            warn!("error playing alert sound: {err}");
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(elapsed_secs: u32, count: u32, since_hit: u32, pace: u32) -> Snapshot {
+        Snapshot {
+            elapsed_secs,
+            duration_secs: 4 * HOUR_SECS,
+            scoring_rule: ScoringRule::default(),
+            count,
+            estimated_pace: Some(pace),
+            time_since_last_hit: Some(since_hit),
+            longest_drought_secs: Some(since_hit),
+        }
+    }
+
+    #[test]
+    fn alerts_test() {
+        let settings = AlertSettings::default();
+        let mut tracker = AlertTracker::default();
+        let target = Some(100);
+        // the first check only takes note, even though it's behind and well into a drought
+        assert!(tracker
+            .check(&settings, &snapshot(1000, 19, 1000, 80), target)
+            .is_empty());
+        assert!(tracker
+            .check(&settings, &snapshot(1100, 19, 1100, 80), target)
+            .is_empty());
+        assert_eq!(
+            tracker.check(&settings, &snapshot(1200, 20, 0, 110), target),
+            [Alert::Milestone { count: 20 }]
+        );
+        assert_eq!(
+            tracker.check(&settings, &snapshot(HOUR_SECS, 21, 900, 90), target),
+            [
+                Alert::Hourly { hour: 1, count: 21 },
+                Alert::BehindTarget {
+                    pace: 90,
+                    target: 100
+                },
+                Alert::Drought { secs: 900 },
+            ]
+        );
+        assert!(tracker
+            .check(&settings, &snapshot(HOUR_SECS + 60, 21, 960, 90), target)
+            .is_empty());
+
+        // jumping past two marks only alerts the later one, and nothing fires at the end
+        assert_eq!(
+            tracker.check(&settings, &snapshot(4 * HOUR_SECS - 600, 30, 0, 90), target),
+            [
+                Alert::Milestone { count: 30 },
+                Alert::Hourly { hour: 3, count: 30 },
+                Alert::FinalHour { mins_left: 10 },
+            ]
+        );
+        assert!(tracker
+            .check(&settings, &snapshot(4 * HOUR_SECS, 30, 600, 90), target)
+            .is_empty());
+
+        let wav = tone_wav(440.0, 2);
+        assert_eq!(&wav[..4], b"RIFF");
+        let data_len = u32::from_le_bytes(wav[40..44].try_into().unwrap());
+        assert_eq!(data_len as usize, wav.len() - 44);
+    }
+}
@@ -0,0 +1,289 @@
+//! How long rounds take, from the time between one entry and the next.
+//!
+//! Time spent paused isn't part of marathon time, so it doesn't show up here. The first entry is
+//! timed from the start of the marathon.
+
+use serde::{Deserialize, Serialize};
+
+use crate::marathon_log::{Entry, EntryKind};
+use crate::scoring::ScoringRule;
+
+/// How many seconds wide each bar of the histogram is, unless set otherwise.
+pub const DEFAULT_BUCKET_SECS: u32 = 10;
+/// How many bars the histogram has, the last one holding every round that's longer.
+pub const HISTOGRAM_BUCKETS: usize = 12;
+
+/// An entry and how long it took since the entry before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundTime {
+    /// The index of the entry in the log.
+    pub index: usize,
+    pub entry: Entry,
+    pub secs: u32,
+}
+
+/// Rounds that took from `start_secs` up to, but not including, `end_secs`. The last bucket has
+/// no end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HistogramBucket {
+    pub start_secs: u32,
+    pub end_secs: Option<u32>,
+    pub count: u32,
+}
+
+/// Time spent on one kind of round.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeSpent {
+    pub rounds: u32,
+    pub secs: u64,
+}
+
+impl TimeSpent {
+    pub fn mean_secs(&self) -> Option<f64> {
+        (self.rounds > 0).then(|| self.secs as f64 / f64::from(self.rounds))
+    }
+
+    fn add(&mut self, secs: u32) {
+        self.rounds += 1;
+        self.secs += u64::from(secs);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimingAnalysis {
+    pub rounds: u32,
+    pub mean_secs: Option<f64>,
+    pub median_secs: Option<u32>,
+    pub histogram: Vec<HistogramBucket>,
+    /// The slowest rounds, slowest first.
+    pub slowest: Vec<RoundTime>,
+    /// Guesses that were hits under the scoring rule, e.g. 5ks.
+    pub hits: TimeSpent,
+    /// Guesses that weren't hits, and rounds without a guess.
+    pub misses: TimeSpent,
+    pub skipped: TimeSpent,
+}
+
+/// A stretch without a hit, from the hit before it, or the start of the marathon, up to the next
+/// hit, or the end of the marathon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Drought {
+    pub start_secs: u32,
+    pub end_secs: u32,
+    /// Entries in between, not counting the hit that ends it.
+    pub rounds: u32,
+}
+
+impl Drought {
+    pub fn secs(&self) -> u32 {
+        self.end_secs.saturating_sub(self.start_secs)
+    }
+}
+
+/// How long a drought can get before the "last 5k" timer turns to a warning, and then to an alarm.
+/// Droughts that reach the alarm are recorded in the log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DroughtThresholds {
+    pub warning_mins: u32,
+    pub alarm_mins: u32,
+}
+
+impl Default for DroughtThresholds {
+    fn default() -> Self {
+        DroughtThresholds {
+            warning_mins: 3,
+            alarm_mins: 6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DroughtLevel {
+    Short,
+    Warning,
+    Alarm,
+}
+
+impl DroughtThresholds {
+    pub fn level(&self, secs: u32) -> DroughtLevel {
+        if secs >= self.alarm_mins * 60 {
+            DroughtLevel::Alarm
+        } else if secs >= self.warning_mins * 60 {
+            DroughtLevel::Warning
+        } else {
+            DroughtLevel::Short
+        }
+    }
+}
+
+/// The droughts between hits in `entries`, in time order, with the last one running up to
+/// `end_secs`. Hits that aren't counted, like late ones, don't end a drought.
+pub fn droughts(
+    entries: impl IntoIterator<Item = Entry>,
+    rule: &ScoringRule,
+    end_secs: u32,
+) -> Vec<Drought> {
+    let mut droughts = Vec::new();
+    let mut current = Drought {
+        start_secs: 0,
+        end_secs: 0,
+        rounds: 0,
+    };
+    for entry in entries {
+        if rule.is_hit(&entry) && entry.timing.counts() {
+            current.end_secs = entry.time_secs;
+            droughts.push(current);
+            current = Drought {
+                start_secs: entry.time_secs,
+                end_secs: 0,
+                rounds: 0,
+            };
+        } else {
+            current.rounds += 1;
+        }
+    }
+    if current.start_secs < end_secs {
+        current.end_secs = end_secs;
+        droughts.push(current);
+    }
+    droughts
+}
+
+/// How long each entry took since the one before, for `entries` in time order.
+pub fn round_times(entries: impl IntoIterator<Item = Entry>) -> Vec<RoundTime> {
+    let mut previous_time = 0;
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let secs = entry.time_secs.saturating_sub(previous_time);
+            previous_time = entry.time_secs;
+            RoundTime { index, entry, secs }
+        })
+        .collect()
+}
+
+/// Analyses the round times of `entries`, with histogram bars `bucket_secs` wide and up to
+/// `slowest_count` of the slowest rounds.
+pub fn analyze_timing(
+    entries: impl IntoIterator<Item = Entry>,
+    rule: &ScoringRule,
+    bucket_secs: u32,
+    slowest_count: usize,
+) -> TimingAnalysis {
+    let round_times = round_times(entries);
+    let bucket_secs = bucket_secs.max(1);
+    let mut histogram: Vec<HistogramBucket> = (0..HISTOGRAM_BUCKETS as u32)
+        .map(|i| HistogramBucket {
+            start_secs: i * bucket_secs,
+            end_secs: (i + 1 < HISTOGRAM_BUCKETS as u32).then_some((i + 1) * bucket_secs),
+            count: 0,
+        })
+        .collect();
+    let mut hits = TimeSpent::default();
+    let mut misses = TimeSpent::default();
+    let mut skipped = TimeSpent::default();
+
+    for round_time in &round_times {
+        let bucket = (round_time.secs / bucket_secs) as usize;
+        histogram[bucket.min(HISTOGRAM_BUCKETS - 1)].count += 1;
+        match round_time.entry.kind {
+            EntryKind::Skipped => skipped.add(round_time.secs),
+            _ if rule.is_hit(&round_time.entry) => hits.add(round_time.secs),
+            _ => misses.add(round_time.secs),
+        }
+    }
+
+    let mut sorted_secs: Vec<u32> = round_times.iter().map(|round| round.secs).collect();
+    sorted_secs.sort_unstable();
+    let total_secs: u64 = sorted_secs.iter().map(|&secs| u64::from(secs)).sum();
+    let rounds = round_times.len() as u32;
+
+    let mut slowest = round_times;
+    // slowest first, and the earliest first among rounds that took as long
+    slowest.sort_by(|a, b| b.secs.cmp(&a.secs).then(a.index.cmp(&b.index)));
+    slowest.truncate(slowest_count);
+
+    TimingAnalysis {
+        rounds,
+        mean_secs: (rounds > 0).then(|| total_secs as f64 / f64::from(rounds)),
+        median_secs: sorted_secs.get(sorted_secs.len() / 2).copied(),
+        histogram,
+        slowest,
+        hits,
+        misses,
+        skipped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marathon_log::EntryTiming;
+
+    #[test]
+    fn timing_test() {
+        let entries = [
+            (5000, 20, EntryKind::Guess),
+            (3000, 35, EntryKind::Guess),
+            (0, 40, EntryKind::Skipped),
+            (5000, 100, EntryKind::Guess),
+            (0, 300, EntryKind::NoGuess),
+        ]
+        .map(|(score, time_secs, kind)| Entry {
+            score,
+            time_secs,
+            kind,
+            player: 0,
+            starts_game: false,
+            timing: EntryTiming::OnTime,
+        });
+        let secs: Vec<u32> = round_times(entries)
+            .iter()
+            .map(|round| round.secs)
+            .collect();
+        assert_eq!(secs, [20, 15, 5, 60, 200]);
+
+        let analysis = analyze_timing(entries, &ScoringRule::default(), 10, 2);
+        assert_eq!(analysis.rounds, 5);
+        assert_eq!(analysis.mean_secs, Some(60.0));
+        assert_eq!(analysis.median_secs, Some(20));
+        let counts: Vec<u32> = analysis.histogram.iter().map(|b| b.count).collect();
+        assert_eq!(counts, [1, 1, 1, 0, 0, 0, 1, 0, 0, 0, 0, 1]);
+        assert_eq!(analysis.histogram[11].end_secs, None);
+        let slowest: Vec<usize> = analysis.slowest.iter().map(|round| round.index).collect();
+        assert_eq!(slowest, [4, 3]);
+        assert_eq!(
+            analysis.hits,
+            TimeSpent {
+                rounds: 2,
+                secs: 80
+            }
+        );
+        assert_eq!(analysis.misses.mean_secs(), Some(107.5));
+        assert_eq!(analysis.skipped.rounds, 1);
+
+        let droughts = droughts(entries, &ScoringRule::default(), 400);
+        let spans: Vec<(u32, u32, u32)> = droughts
+            .iter()
+            .map(|d| (d.start_secs, d.end_secs, d.rounds))
+            .collect();
+        assert_eq!(spans, [(0, 20, 0), (20, 100, 2), (100, 400, 1)]);
+        assert_eq!(droughts[2].secs(), 300);
+
+        let thresholds = DroughtThresholds::default();
+        let levels: Vec<DroughtLevel> = droughts
+            .iter()
+            .map(|d| thresholds.level(d.secs()))
+            .collect();
+        assert_eq!(
+            levels,
+            [
+                DroughtLevel::Short,
+                DroughtLevel::Short,
+                DroughtLevel::Warning
+            ]
+        );
+    }
+}
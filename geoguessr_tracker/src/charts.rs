@@ -0,0 +1,793 @@
+//! Charts of a run, drawn as SVG or PNG entirely offline, for sharing results and comparing runs.
+//!
+//! A chart is a list of shapes in chart units, so both formats are drawn from the same layout.
+//! PNG text uses the tracker's own font, built into the program, so no font has to be installed.
+
+use std::fs;
+
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use tiny_skia::{
+    Color, Paint, PathBuilder, Pixmap, PremultipliedColorU8, Rect, Stroke, StrokeDash, Transform,
+};
+
+use crate::error::{PersistError, RenderError};
+use crate::i18n::{fmt_decimal, fmt_int, tr, trf};
+use crate::marathon_log::{Entry, EntryKind, MarathonLog};
+use crate::report::escape_html;
+use crate::scoring::{ScoringRule, Tally};
+use crate::splits::HOUR_SECS;
+
+pub const CHART_WIDTH: f32 = 800.0;
+pub const CHART_HEIGHT: f32 = 400.0;
+/// Pixels per chart unit in PNGs, so they stay sharp on high resolution screens.
+pub const PNG_SCALE: f32 = 2.0;
+/// How far back the rolling pace looks.
+pub const DEFAULT_PACE_WINDOW_SECS: u32 = HOUR_SECS;
+/// How often the rolling pace is sampled.
+const PACE_SAMPLE_SECS: u32 = 5 * 60;
+/// How many points wide each bar of the score distribution is, below 5000.
+const SCORE_BUCKET: u16 = 500;
+/// The steps tried between time axis labels, so they land on whole hours where possible.
+const TIME_STEPS_MINS: [u32; 12] = [1, 2, 5, 10, 15, 30, 60, 120, 180, 240, 360, 720];
+const MAX_TIME_TICKS: u32 = 8;
+/// The font used for text in PNGs.
+const FONT_DATA: &[u8] = include_bytes!("fonts/RecursiveSansLnrSt-Bold.ttf");
+const FONT_SIZE: f32 = 13.0;
+const TITLE_SIZE: f32 = 18.0;
+
+// room around the plot for the title, legend and axis labels
+const LEFT: f32 = 64.0;
+const RIGHT: f32 = 24.0;
+const TOP: f32 = 56.0;
+const BOTTOM: f32 = 40.0;
+
+const TEXT_COLOR: Rgb = Rgb(0x22, 0x22, 0x22);
+const AXIS_COLOR: Rgb = Rgb(0x44, 0x44, 0x44);
+const GRID_COLOR: Rgb = Rgb(0xdd, 0xdd, 0xdd);
+const RUN_COLOR: Rgb = Rgb(0x1f, 0x77, 0xb4);
+const PREVIOUS_COLOR: Rgb = Rgb(0xff, 0x7f, 0x0e);
+const TARGET_COLOR: Rgb = Rgb(0x88, 0x88, 0x88);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    fn hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+/// Which part of a text is at its position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+/// Something drawn on a chart, in chart units from the top left corner.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Line {
+        points: Vec<(f32, f32)>,
+        color: Rgb,
+        width: f32,
+        dashed: bool,
+    },
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        color: Rgb,
+    },
+    /// Text with its baseline at `y`.
+    Text {
+        x: f32,
+        y: f32,
+        text: String,
+        size: f32,
+        anchor: Anchor,
+        color: Rgb,
+    },
+}
+
+/// A chart ready to be drawn, `CHART_WIDTH` by `CHART_HEIGHT` units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chart {
+    pub title: String,
+    pub shapes: Vec<Shape>,
+}
+
+/// Where data values end up on the chart, from 0 up to `x_max` and `y_max`.
+#[derive(Debug, Clone, Copy)]
+struct PlotArea {
+    x_max: f64,
+    y_max: f64,
+}
+
+impl PlotArea {
+    fn point(&self, x: f64, y: f64) -> (f32, f32) {
+        let width = CHART_WIDTH - LEFT - RIGHT;
+        let height = CHART_HEIGHT - TOP - BOTTOM;
+        (
+            LEFT + (x / self.x_max) as f32 * width,
+            CHART_HEIGHT - BOTTOM - (y / self.y_max) as f32 * height,
+        )
+    }
+}
+
+impl Chart {
+    /// An empty chart with a title, axes, grid lines at `y_ticks` and labels at `x_ticks`. The
+    /// name of the y axis goes above it.
+    fn with_axes(
+        title: &str,
+        y_name: &str,
+        area: PlotArea,
+        x_ticks: &[(f64, String)],
+        y_ticks: &[(f64, String)],
+    ) -> Self {
+        let mut shapes = Vec::new();
+        for (y, label) in y_ticks {
+            let (left, y) = area.point(0.0, *y);
+            let (right, _) = area.point(area.x_max, 0.0);
+            shapes.push(Shape::Line {
+                points: vec![(left, y), (right, y)],
+                color: GRID_COLOR,
+                width: 1.0,
+                dashed: false,
+            });
+            shapes.push(text(left - 6.0, y + 4.0, label, FONT_SIZE, Anchor::End));
+        }
+        for (x, label) in x_ticks {
+            let (x, bottom) = area.point(*x, 0.0);
+            shapes.push(text(x, bottom + 18.0, label, FONT_SIZE, Anchor::Middle));
+        }
+        let (left, bottom) = area.point(0.0, 0.0);
+        let (right, top) = area.point(area.x_max, area.y_max);
+        shapes.push(Shape::Line {
+            points: vec![(left, top), (left, bottom), (right, bottom)],
+            color: AXIS_COLOR,
+            width: 1.0,
+            dashed: false,
+        });
+        shapes.push(text(left, top - 10.0, y_name, FONT_SIZE, Anchor::Start));
+        shapes.push(text(LEFT, 24.0, title, TITLE_SIZE, Anchor::Start));
+        Chart {
+            title: title.to_owned(),
+            shapes,
+        }
+    }
+
+    /// Adds a legend in the top right corner, one colored name after another. The names are
+    /// translated.
+    fn add_legend(&mut self, items: &[(&str, Rgb)]) {
+        let mut x = CHART_WIDTH - RIGHT;
+        for (name, color) in items.iter().rev() {
+            self.shapes.push(Shape::Text {
+                x,
+                y: 24.0,
+                text: tr(name),
+                size: FONT_SIZE,
+                anchor: Anchor::End,
+                color: *color,
+            });
+            // roughly the width of the name, the exact width depends on the font
+            x -= name.len() as f32 * FONT_SIZE * 0.5 + 16.0;
+        }
+    }
+
+    pub fn to_svg(&self) -> String {
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{CHART_WIDTH}\" \
+            height=\"{CHART_HEIGHT}\" viewBox=\"0 0 {CHART_WIDTH} {CHART_HEIGHT}\" role=\"img\" \
+            aria-label=\"{}\" font-family=\"Ubuntu, sans-serif\">\n\
+            <rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>\n",
+            escape_html(&self.title)
+        );
+        for shape in &self.shapes {
+            match shape {
+                Shape::Line {
+                    points,
+                    color,
+                    width,
+                    dashed,
+                } => {
+                    let points: Vec<String> = points
+                        .iter()
+                        .map(|(x, y)| format!("{x:.1},{y:.1}"))
+                        .collect();
+                    let dash = if *dashed {
+                        " stroke-dasharray=\"6 4\""
+                    } else {
+                        ""
+                    };
+                    svg += &format!(
+                        "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"{width}\"{dash} \
+                        points=\"{}\"/>\n",
+                        color.hex(),
+                        points.join(" ")
+                    );
+                }
+                Shape::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    color,
+                } => {
+                    svg += &format!(
+                        "<rect x=\"{x:.1}\" y=\"{y:.1}\" width=\"{width:.1}\" \
+                        height=\"{height:.1}\" fill=\"{}\"/>\n",
+                        color.hex()
+                    );
+                }
+                Shape::Text {
+                    x,
+                    y,
+                    text,
+                    size,
+                    anchor,
+                    color,
+                } => {
+                    let anchor = match anchor {
+                        Anchor::Start => "start",
+                        Anchor::Middle => "middle",
+                        Anchor::End => "end",
+                    };
+                    svg += &format!(
+                        "<text x=\"{x:.1}\" y=\"{y:.1}\" font-size=\"{size}\" \
+                        text-anchor=\"{anchor}\" fill=\"{}\">{}</text>\n",
+                        color.hex(),
+                        escape_html(text)
+                    );
+                }
+            }
+        }
+        svg += "</svg>\n";
+        svg
+    }
+
+    /// The chart as PNG data, `PNG_SCALE` pixels per unit. Text is left out if the font can't be
+    /// loaded.
+    pub fn to_png(&self) -> Result<Vec<u8>, RenderError> {
+        let width = (CHART_WIDTH * PNG_SCALE) as u32;
+        let height = (CHART_HEIGHT * PNG_SCALE) as u32;
+        let mut pixmap = Pixmap::new(width, height).ok_or(RenderError::EmptyImage)?;
+        pixmap.fill(Color::WHITE);
+        let transform = Transform::from_scale(PNG_SCALE, PNG_SCALE);
+        let font = FontRef::try_from_slice(FONT_DATA);
+        if let Err(err) = &font {
+            warn!("chart font could not be loaded, drawing the chart without text: {err}");
+        }
+        let font = font.ok();
+        for shape in &self.shapes {
+            match shape {
+                Shape::Line {
+                    points,
+                    color,
+                    width,
+                    dashed,
+                } => {
+                    let mut builder = PathBuilder::new();
+                    for (i, &(x, y)) in points.iter().enumerate() {
+                        match i {
+                            0 => builder.move_to(x, y),
+                            _ => builder.line_to(x, y),
+                        }
+                    }
+                    let Some(path) = builder.finish() else {
+                        continue;
+                    };
+                    let stroke = Stroke {
+                        width: *width,
+                        dash: dashed
+                            .then(|| StrokeDash::new(vec![6.0, 4.0], 0.0))
+                            .flatten(),
+                        ..Stroke::default()
+                    };
+                    pixmap.stroke_path(&path, &paint(*color), &stroke, transform, None);
+                }
+                Shape::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    color,
+                } => {
+                    if let Some(rect) = Rect::from_xywh(*x, *y, *width, *height) {
+                        pixmap.fill_rect(rect, &paint(*color), transform, None);
+                    }
+                }
+                Shape::Text {
+                    x,
+                    y,
+                    text,
+                    size,
+                    anchor,
+                    color,
+                } => {
+                    if let Some(font) = &font {
+                        draw_text(&mut pixmap, font, (*x, *y), text, *size, *anchor, *color);
+                    }
+                }
+            }
+        }
+        pixmap
+            .encode_png()
+            .map_err(|err| RenderError::Encode(Box::new(err)))
+    }
+
+    /// Writes the chart to `path` as SVG, replacing the file if it exists.
+    pub fn save_svg(&self, path: &str) -> Result<(), PersistError> {
+        fs::write(path, self.to_svg()).map_err(|err| PersistError::io(path, err))
+    }
+
+    /// Writes the chart to `path` as PNG, replacing the file if it exists.
+    pub fn save_png(&self, path: &str) -> Result<(), PersistError> {
+        let png = self.to_png().map_err(|source| PersistError::Render {
+            path: path.into(),
+            source,
+        })?;
+        fs::write(path, png).map_err(|err| PersistError::io(path, err))
+    }
+}
+
+fn text(x: f32, y: f32, text: &str, size: f32, anchor: Anchor) -> Shape {
+    Shape::Text {
+        x,
+        y,
+        text: text.to_owned(),
+        size,
+        anchor,
+        color: TEXT_COLOR,
+    }
+}
+
+fn paint(color: Rgb) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(color.0, color.1, color.2, 255);
+    paint.anti_alias = true;
+    paint
+}
+
+/// Draws `text` with its baseline at `at`, in chart units.
+fn draw_text(
+    pixmap: &mut Pixmap,
+    font: &FontRef,
+    at: (f32, f32),
+    text: &str,
+    size: f32,
+    anchor: Anchor,
+    color: Rgb,
+) {
+    let scale = PxScale::from(size * PNG_SCALE);
+    let scaled = font.as_scaled(scale);
+    let width: f32 = text
+        .chars()
+        .map(|c| scaled.h_advance(font.glyph_id(c)))
+        .sum();
+    let mut caret = at.0 * PNG_SCALE
+        - match anchor {
+            Anchor::Start => 0.0,
+            Anchor::Middle => width / 2.0,
+            Anchor::End => width,
+        };
+    let baseline = at.1 * PNG_SCALE;
+    let (pixmap_width, pixmap_height) = (pixmap.width() as i32, pixmap.height() as i32);
+    let pixels = pixmap.pixels_mut();
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        let glyph = id.with_scale_and_position(scale, point(caret, baseline));
+        caret += scaled.h_advance(id);
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|glyph_x, glyph_y, coverage| {
+            let x = bounds.min.x as i32 + glyph_x as i32;
+            let y = bounds.min.y as i32 + glyph_y as i32;
+            if (0..pixmap_width).contains(&x) && (0..pixmap_height).contains(&y) {
+                let i = (y * pixmap_width + x) as usize;
+                pixels[i] = blend(pixels[i], color, coverage);
+            }
+        });
+    }
+}
+
+/// `color` drawn over `dst` with `coverage` from 0 to 1.
+fn blend(dst: PremultipliedColorU8, color: Rgb, coverage: f32) -> PremultipliedColorU8 {
+    let coverage = coverage.clamp(0.0, 1.0);
+    let mix = |src: u8, dst: u8| {
+        (f32::from(src) * coverage + f32::from(dst) * (1.0 - coverage)).round() as u8
+    };
+    PremultipliedColorU8::from_rgba(
+        mix(color.0, dst.red()),
+        mix(color.1, dst.green()),
+        mix(color.2, dst.blue()),
+        mix(255, dst.alpha()),
+    )
+    .unwrap_or(dst)
+}
+
+/// A round step size for about `max_ticks` ticks from 0 to `max`: 1, 2 or 5 times a power of 10.
+fn nice_step(max: f64, max_ticks: u32) -> f64 {
+    let raw = (max / f64::from(max_ticks)).max(f64::MIN_POSITIVE);
+    let magnitude = 10_f64.powf(raw.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|multiple| multiple * magnitude)
+        .find(|&step| step >= raw)
+        .unwrap_or(10.0 * magnitude)
+}
+
+/// Ticks for a count axis, and the top of the axis rounded up to the last tick.
+fn count_ticks(max: f64) -> (f64, Vec<(f64, String)>) {
+    let step = nice_step(max.max(1.0), 5);
+    let top = (max.max(1.0) / step).ceil() * step;
+    let ticks = (0..)
+        .map(|i| f64::from(i) * step)
+        .take_while(|&value| value <= top + step / 2.0)
+        .map(|value| match step < 1.0 {
+            true => (value, fmt_decimal(value, 1)),
+            false => (value, fmt_decimal(value, 0)),
+        })
+        .collect();
+    (top, ticks)
+}
+
+/// Ticks for a time axis from 0 to `max_secs`, at whole hours or a round number of minutes.
+fn time_ticks(max_secs: u32) -> Vec<(f64, String)> {
+    let step_mins = TIME_STEPS_MINS
+        .into_iter()
+        .find(|step_mins| max_secs / (step_mins * 60) <= MAX_TIME_TICKS)
+        .unwrap_or(TIME_STEPS_MINS[TIME_STEPS_MINS.len() - 1]);
+    (0..)
+        .map(|i| i * step_mins * 60)
+        .take_while(|&secs| secs <= max_secs)
+        .map(|secs| {
+            let (hours, mins) = (secs / HOUR_SECS, secs % HOUR_SECS / 60);
+            match mins {
+                0 => (f64::from(secs), format!("{hours}h")),
+                _ => (f64::from(secs), format!("{hours}:{mins:02}")),
+            }
+        })
+        .collect()
+}
+
+/// The count after each entry that changed it, starting from 0 at the start of the marathon.
+pub fn count_steps(
+    entries: impl IntoIterator<Item = Entry>,
+    rule: &ScoringRule,
+) -> Vec<(u32, u32)> {
+    let mut tally = Tally::default();
+    let mut steps = vec![(0, 0)];
+    for entry in entries {
+        let before = tally.count;
+        tally.add(rule, &entry);
+        if tally.count != before {
+            steps.push((entry.time_secs, tally.count));
+        }
+    }
+    steps
+}
+
+/// The count at `time_secs`, from steps made by `count_steps`.
+fn count_at(steps: &[(u32, u32)], time_secs: u32) -> u32 {
+    let i = steps.partition_point(|&(step_secs, _)| step_secs <= time_secs);
+    i.checked_sub(1).map_or(0, |i| steps[i].1)
+}
+
+/// The count per hour over the `window_secs` before each sample, up to `end_secs`. Early on the
+/// window only goes back to the start of the marathon.
+pub fn rolling_pace(steps: &[(u32, u32)], window_secs: u32, end_secs: u32) -> Vec<(u32, f64)> {
+    let window_secs = window_secs.max(1);
+    (1..)
+        .map(|i| i * PACE_SAMPLE_SECS)
+        .take_while(|&secs| secs <= end_secs)
+        .map(|secs| {
+            let window = window_secs.min(secs);
+            let gained = count_at(steps, secs) - count_at(steps, secs - window);
+            let per_hour = f64::from(gained) * f64::from(HOUR_SECS) / f64::from(window);
+            (secs, per_hour)
+        })
+        .collect()
+}
+
+/// The number of guesses in each 500 point range, with 5000 in a bar of its own.
+pub fn score_distribution(entries: impl IntoIterator<Item = Entry>) -> Vec<u32> {
+    let mut buckets = vec![0; usize::from(5000 / SCORE_BUCKET) + 1];
+    for entry in entries {
+        if entry.kind == EntryKind::Guess {
+            buckets[usize::from(entry.score.min(5000) / SCORE_BUCKET)] += 1;
+        }
+    }
+    buckets
+}
+
+/// The count over the run as a step line, with the pace needed for the target as a dashed line
+/// and `previous` counted under the same rule.
+pub fn cumulative_chart(marathon_log: &MarathonLog, previous: Option<&MarathonLog>) -> Chart {
+    let rule = marathon_log.scoring_rule();
+    let steps = count_steps(marathon_log.entries(), &rule);
+    let previous_steps = previous.map(|previous| {
+        let steps = count_steps(previous.entries(), &rule);
+        (steps, previous.played_secs())
+    });
+    let end_secs = previous_steps
+        .as_ref()
+        .map_or(0, |(_, end)| *end)
+        .max(marathon_log.played_secs())
+        .max(1);
+    let target_at_end = marathon_log.metadata().target.map(|target| {
+        f64::from(target) * f64::from(end_secs) / f64::from(marathon_log.duration_secs().max(1))
+    });
+    let max_count = steps
+        .iter()
+        .chain(previous_steps.iter().flat_map(|(steps, _)| steps))
+        .map(|&(_, count)| f64::from(count))
+        .fold(target_at_end.unwrap_or(0.0), f64::max);
+    let (y_max, y_ticks) = count_ticks(max_count);
+    let area = PlotArea {
+        x_max: f64::from(end_secs),
+        y_max,
+    };
+
+    let title = trf("{0} over time", &[&rule.count_label()]);
+    let mut chart = Chart::with_axes(
+        &title,
+        &rule.count_label(),
+        area,
+        &time_ticks(end_secs),
+        &y_ticks,
+    );
+    let mut legend = vec![("This run", RUN_COLOR)];
+    if let Some(target_at_end) = target_at_end {
+        chart.shapes.push(Shape::Line {
+            points: vec![area.point(0.0, 0.0), area.point(area.x_max, target_at_end)],
+            color: TARGET_COLOR,
+            width: 1.5,
+            dashed: true,
+        });
+        legend.push(("Target", TARGET_COLOR));
+    }
+    if let Some((previous_steps, previous_end)) = &previous_steps {
+        chart.shapes.push(step_line(
+            area,
+            previous_steps,
+            *previous_end,
+            PREVIOUS_COLOR,
+        ));
+        legend.push(("Previous run", PREVIOUS_COLOR));
+    }
+    chart.shapes.push(step_line(
+        area,
+        &steps,
+        marathon_log.played_secs(),
+        RUN_COLOR,
+    ));
+    chart.add_legend(&legend);
+    chart
+}
+
+/// A line through `steps` that stays flat between them, up to `end_secs`.
+fn step_line(area: PlotArea, steps: &[(u32, u32)], end_secs: u32, color: Rgb) -> Shape {
+    let mut points = Vec::with_capacity(steps.len() * 2 + 1);
+    let mut previous_count = 0;
+    for &(secs, count) in steps {
+        points.push(area.point(f64::from(secs), f64::from(previous_count)));
+        points.push(area.point(f64::from(secs), f64::from(count)));
+        previous_count = count;
+    }
+    points.push(area.point(f64::from(end_secs), f64::from(previous_count)));
+    Shape::Line {
+        points,
+        color,
+        width: 2.0,
+        dashed: false,
+    }
+}
+
+/// The count per hour over the last `window_secs`, with the pace needed for the target as a
+/// dashed line and `previous` counted under the same rule.
+pub fn pace_chart(
+    marathon_log: &MarathonLog,
+    window_secs: u32,
+    previous: Option<&MarathonLog>,
+) -> Chart {
+    let rule = marathon_log.scoring_rule();
+    let pace = |log: &MarathonLog| {
+        let steps = count_steps(log.entries(), &rule);
+        rolling_pace(&steps, window_secs, log.played_secs())
+    };
+    let run_pace = pace(marathon_log);
+    let previous_pace = previous.map(pace);
+    let end_secs = run_pace
+        .iter()
+        .chain(previous_pace.iter().flatten())
+        .map(|&(secs, _)| secs)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let target_pace = marathon_log.metadata().target.map(|target| {
+        f64::from(target) * f64::from(HOUR_SECS) / f64::from(marathon_log.duration_secs().max(1))
+    });
+    let max_pace = run_pace
+        .iter()
+        .chain(previous_pace.iter().flatten())
+        .map(|&(_, pace)| pace)
+        .fold(target_pace.unwrap_or(0.0), f64::max);
+    let (y_max, y_ticks) = count_ticks(max_pace);
+    let area = PlotArea {
+        x_max: f64::from(end_secs),
+        y_max,
+    };
+
+    let window_mins = window_secs / 60;
+    let title = trf("Pace over the last {0} minutes", &[&fmt_int(window_mins)]);
+    let y_name = trf("{0} per hour", &[&rule.count_label()]);
+    let mut chart = Chart::with_axes(&title, &y_name, area, &time_ticks(end_secs), &y_ticks);
+    let mut legend = vec![("This run", RUN_COLOR)];
+    if let Some(target_pace) = target_pace {
+        chart.shapes.push(Shape::Line {
+            points: vec![
+                area.point(0.0, target_pace),
+                area.point(area.x_max, target_pace),
+            ],
+            color: TARGET_COLOR,
+            width: 1.5,
+            dashed: true,
+        });
+        legend.push(("Target", TARGET_COLOR));
+    }
+    let line = |pace: &[(u32, f64)], color| Shape::Line {
+        points: pace
+            .iter()
+            .map(|&(secs, pace)| area.point(f64::from(secs), pace))
+            .collect(),
+        color,
+        width: 2.0,
+        dashed: false,
+    };
+    if let Some(previous_pace) = &previous_pace {
+        chart.shapes.push(line(previous_pace, PREVIOUS_COLOR));
+        legend.push(("Previous run", PREVIOUS_COLOR));
+    }
+    chart.shapes.push(line(&run_pace, RUN_COLOR));
+    chart.add_legend(&legend);
+    chart
+}
+
+/// How many guesses scored in each 500 point range.
+pub fn score_chart(marathon_log: &MarathonLog) -> Chart {
+    let buckets = score_distribution(marathon_log.entries());
+    let max_count = buckets.iter().copied().max().unwrap_or(0);
+    let (y_max, y_ticks) = count_ticks(f64::from(max_count));
+    let area = PlotArea {
+        x_max: buckets.len() as f64,
+        y_max,
+    };
+    let x_ticks: Vec<(f64, String)> = (0..buckets.len())
+        .map(|i| {
+            let low = i as u16 * SCORE_BUCKET;
+            let label = match low {
+                5000 => "5000".to_owned(),
+                _ => format!("{low}+"),
+            };
+            (i as f64 + 0.5, label)
+        })
+        .collect();
+    let mut chart = Chart::with_axes(
+        &tr("Score distribution"),
+        &tr("Guesses"),
+        area,
+        &x_ticks,
+        &y_ticks,
+    );
+    for (i, &count) in buckets.iter().enumerate() {
+        let (left, top) = area.point(i as f64, f64::from(count));
+        let (right, bottom) = area.point(i as f64 + 1.0, 0.0);
+        chart.shapes.push(Shape::Rect {
+            x: left + 2.0,
+            y: top,
+            width: right - left - 4.0,
+            height: bottom - top,
+            color: RUN_COLOR,
+        });
+    }
+    chart
+}
+
+/// The charts for a run, by the name they're saved under.
+pub fn run_charts(
+    marathon_log: &MarathonLog,
+    previous: Option<&MarathonLog>,
+) -> [(&'static str, Chart); 3] {
+    [
+        ("cumulative", cumulative_chart(marathon_log, previous)),
+        (
+            "pace",
+            pace_chart(marathon_log, DEFAULT_PACE_WINDOW_SECS, previous),
+        ),
+        ("scores", score_chart(marathon_log)),
+    ]
+}
+
+/// Saves every chart of a run as `{prefix}_{name}.svg` and `.png`, and returns the paths
+/// written.
+pub fn save_charts(
+    marathon_log: &MarathonLog,
+    previous: Option<&MarathonLog>,
+    prefix: &str,
+) -> Result<Vec<String>, PersistError> {
+    let mut paths = Vec::new();
+    for (name, chart) in run_charts(marathon_log, previous) {
+        let svg_path = format!("{prefix}_{name}.svg");
+        chart.save_svg(&svg_path)?;
+        paths.push(svg_path);
+        let png_path = format!("{prefix}_{name}.png");
+        chart.save_png(&png_path)?;
+        paths.push(png_path);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marathon_log::EntryTiming;
+
+    #[test]
+    fn charts_test() {
+        let entries =
+            [(5000, 600), (1200, 700), (5000, 1200), (5000, 4000)].map(|(score, time_secs)| {
+                Entry {
+                    score,
+                    time_secs,
+                    kind: EntryKind::Guess,
+                    player: 0,
+                    starts_game: false,
+                    timing: EntryTiming::OnTime,
+                }
+            });
+        let rule = ScoringRule::default();
+        let steps = count_steps(entries, &rule);
+        assert_eq!(steps, [(0, 0), (600, 1), (1200, 2), (4000, 3)]);
+        assert_eq!(count_at(&steps, 599), 0);
+        assert_eq!(count_at(&steps, 1200), 2);
+        let pace = rolling_pace(&steps, HOUR_SECS, 4200);
+        // 1 in the first 10 minutes, then 2 in the first 20
+        assert_eq!(pace[1], (600, 6.0));
+        assert_eq!(pace[3], (1200, 6.0));
+        // 1 between 0:10 and 1:10
+        assert_eq!(pace[13], (4200, 2.0));
+        assert_eq!(
+            score_distribution(entries),
+            [0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 3]
+        );
+        assert_eq!(nice_step(23.0, 5), 5.0);
+        assert_eq!(nice_step(0.3, 5), 0.1);
+        let hours: Vec<String> = time_ticks(24 * HOUR_SECS)
+            .into_iter()
+            .map(|(_, label)| label)
+            .collect();
+        assert_eq!(
+            hours,
+            ["0h", "3h", "6h", "9h", "12h", "15h", "18h", "21h", "24h"]
+        );
+        assert_eq!(time_ticks(1200)[1].1, "0:05");
+
+        let mut marathon_log = MarathonLog::from_entries(8000, entries);
+        marathon_log.metadata_mut().target = Some(10);
+        let previous = MarathonLog::from_entries(8000, [entries[0]]);
+        let chart = cumulative_chart(&marathon_log, Some(&previous));
+        let svg = chart.to_svg();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("Previous run"));
+        assert!(svg.contains("stroke-dasharray"));
+        let png = score_chart(&marathon_log).to_png().unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    }
+}
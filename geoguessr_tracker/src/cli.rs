@@ -0,0 +1,199 @@
+//! Commands for working with saved logs without opening the tracker window.
+
+use std::str::FromStr;
+
+use geoguessr_tracker::analysis::{analyze_timing, TimeSpent, TimingAnalysis, DEFAULT_BUCKET_SECS};
+use geoguessr_tracker::charts::save_charts;
+use geoguessr_tracker::export::{entries_csv, splits_csv, write_csv};
+use geoguessr_tracker::marathon_log::MarathonLog;
+use geoguessr_tracker::merge::{merge_files, split_log, MergeOptions};
+use geoguessr_tracker::report::{html_report, write_report};
+use geoguessr_tracker::settings::{Settings, SETTINGS_FILE};
+use geoguessr_tracker::splits::{log_splits, HOUR_SECS};
+use geoguessr_tracker::utils::time_counter;
+use geoguessr_tracker::utils::timekeeping::HoursMinutesSeconds;
+
+const USAGE: &str = "usage:
+    geoguessr_tracker merge <out.ron> <in.ron>... [--rebase]
+    geoguessr_tracker split <in.ron> <HH:MM:SS> <before.ron> <after.ron>
+    geoguessr_tracker analyze <in.ron> [--bucket <secs>]
+    geoguessr_tracker export <in.ron> <entries.csv> <splits.csv> [--interval <mins>] [--compare <previous.ron>]
+    geoguessr_tracker report <in.ron> <out.html>
+    geoguessr_tracker charts <in.ron> <out-prefix> [--compare <previous.ron>]";
+
+/// How many of the slowest rounds `analyze` lists.
+const SLOWEST_COUNT: usize = 10;
+/// How many characters wide the longest histogram bar is.
+const BAR_WIDTH: u32 = 40;
+
+/// The marathon length from the settings, for logs saved without their length.
+fn default_duration_secs() -> u32 {
+    Settings::load_or_default(SETTINGS_FILE).duration_secs
+}
+
+fn load_log(path: &str) -> Result<MarathonLog, String> {
+    MarathonLog::from_file(default_duration_secs(), path).map_err(|err| err.to_string())
+}
+
+/// Runs the command in `args` (without the program name), returning the message to print on
+/// failure.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["merge", out_path, rest @ ..] => {
+            let rebase = rest.contains(&"--rebase");
+            let in_paths: Vec<&str> = rest
+                .iter()
+                .copied()
+                .filter(|arg| *arg != "--rebase")
+                .collect();
+            if in_paths.is_empty() {
+                return Err(USAGE.to_owned());
+            }
+            let report = merge_files(&in_paths, MergeOptions { rebase }, default_duration_secs())
+                .map_err(|err| err.to_string())?;
+            report
+                .merged
+                .save_to_path(out_path)
+                .map_err(|err| err.to_string())?;
+            println!("{}", report.summary());
+            println!("saved to {out_path}");
+            Ok(())
+        }
+        ["split", in_path, at, before_path, after_path] => {
+            let at_secs = HoursMinutesSeconds::from_str(at)
+                .map_err(|err| format!("invalid split time {at}: {err}"))?
+                .total_secs();
+            let marathon_log = load_log(in_path)?;
+            let (before, after) = split_log(&marathon_log, at_secs);
+            for (part, path) in [(before, before_path), (after, after_path)] {
+                part.save_to_path(path).map_err(|err| err.to_string())?;
+                println!("saved {} entries to {path}", part.entries().count());
+            }
+            Ok(())
+        }
+        ["analyze", in_path, rest @ ..] => {
+            let bucket_secs = match rest {
+                [] => DEFAULT_BUCKET_SECS,
+                ["--bucket", secs] => {
+                    u32::from_str(secs).map_err(|err| format!("invalid bucket size: {err}"))?
+                }
+                _ => return Err(USAGE.to_owned()),
+            };
+            let marathon_log = load_log(in_path)?;
+            let rule = marathon_log.scoring_rule();
+            let analysis =
+                analyze_timing(marathon_log.entries(), &rule, bucket_secs, SLOWEST_COUNT);
+            print_timing(&analysis, &rule.hit_name());
+            Ok(())
+        }
+        ["export", in_path, entries_path, splits_path, rest @ ..] => {
+            let mut interval_mins = HOUR_SECS / 60;
+            let mut previous = None;
+            for option in rest.chunks(2) {
+                match option {
+                    ["--interval", mins] => {
+                        interval_mins = u32::from_str(mins)
+                            .map_err(|err| format!("invalid interval: {err}"))?;
+                    }
+                    ["--compare", path] => {
+                        let previous_log = load_log(path)?;
+                        previous = Some(previous_log);
+                    }
+                    _ => return Err(USAGE.to_owned()),
+                }
+            }
+            let marathon_log = load_log(in_path)?;
+            let comparisons = log_splits(
+                &marathon_log,
+                interval_mins * 60,
+                marathon_log.duration_secs(),
+                previous.as_ref(),
+            );
+            write_csv(entries_path, &entries_csv(&marathon_log)).map_err(|err| err.to_string())?;
+            write_csv(splits_path, &splits_csv(&comparisons)).map_err(|err| err.to_string())?;
+            println!("exported to {entries_path} and {splits_path}");
+            Ok(())
+        }
+        ["report", in_path, out_path] => {
+            let marathon_log = load_log(in_path)?;
+            write_report(out_path, &html_report(&marathon_log)).map_err(|err| err.to_string())?;
+            println!("report saved to {out_path}");
+            Ok(())
+        }
+        ["charts", in_path, prefix, rest @ ..] => {
+            let previous = match rest {
+                [] => None,
+                ["--compare", path] => Some(load_log(path)?),
+                _ => return Err(USAGE.to_owned()),
+            };
+            let marathon_log = load_log(in_path)?;
+            let paths = save_charts(&marathon_log, previous.as_ref(), prefix)
+                .map_err(|err| err.to_string())?;
+            for path in paths {
+                println!("saved {path}");
+            }
+            Ok(())
+        }
+        _ => Err(USAGE.to_owned()),
+    }
+}
+
+fn print_timing(analysis: &TimingAnalysis, hit_name: &str) {
+    let mean = analysis
+        .mean_secs
+        .map_or("-".to_owned(), |secs| format!("{secs:.1}s"));
+    let median = analysis
+        .median_secs
+        .map_or("-".to_owned(), |secs| format!("{secs}s"));
+    println!("rounds: {}", analysis.rounds);
+    println!("mean round: {mean}, median round: {median}");
+    print_time_spent(&format!("{hit_name} rounds"), &analysis.hits);
+    print_time_spent("missed rounds", &analysis.misses);
+    print_time_spent("skipped games", &analysis.skipped);
+
+    println!();
+    println!("round times:");
+    let max_count = analysis
+        .histogram
+        .iter()
+        .map(|b| b.count)
+        .max()
+        .unwrap_or(0);
+    for bucket in &analysis.histogram {
+        let range = match bucket.end_secs {
+            Some(end) => format!("{}-{end}s", bucket.start_secs),
+            None => format!("{}s+", bucket.start_secs),
+        };
+        let bar_len = match max_count {
+            0 => 0,
+            _ => bucket.count * BAR_WIDTH / max_count,
+        };
+        let bar = "#".repeat(bar_len as usize);
+        println!("  {range:>11} {bar} {}", bucket.count);
+    }
+
+    println!();
+    println!("slowest rounds:");
+    for round in &analysis.slowest {
+        println!(
+            "  entry {} at {}: {}s, {:?} with {} points",
+            round.index + 1,
+            time_counter(round.entry.time_secs),
+            round.secs,
+            round.entry.kind,
+            round.entry.score
+        );
+    }
+}
+
+fn print_time_spent(name: &str, time_spent: &TimeSpent) {
+    let mean = time_spent
+        .mean_secs()
+        .map_or("-".to_owned(), |secs| format!("{secs:.1}s"));
+    let total = time_counter(u32::try_from(time_spent.secs).unwrap_or(u32::MAX));
+    println!(
+        "{name}: {}, {total} in total, {mean} on average",
+        time_spent.rounds
+    );
+}
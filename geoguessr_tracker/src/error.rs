@@ -0,0 +1,242 @@
+//! Error types for everything that can go wrong while tracking a marathon.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::i18n::{tr, trf};
+use crate::marathon_log::MAX_PLAYERS;
+use crate::parsing::ParseError;
+
+/// Any error the tracker can run into.
+#[derive(Debug)]
+pub enum TrackerError {
+    Parse(ParseError),
+    /// A score was valid on its own, but not for the kind of entry it was added as.
+    ImpossibleScore {
+        score: u16,
+    },
+    Persist(PersistError),
+    Clock(ClockError),
+}
+
+/// Errors from saving and loading log files.
+#[derive(Debug)]
+pub enum PersistError {
+    NotFound {
+        path: PathBuf,
+    },
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// The file was read, but isn't a valid log. The line and column are where RON gave up.
+    Deserialize {
+        path: PathBuf,
+        line: usize,
+        col: usize,
+        source: ron::Error,
+    },
+    Serialize(ron::Error),
+    /// Every file name the tracker saves to already exists.
+    AllFileNamesTaken {
+        first: PathBuf,
+        last: PathBuf,
+    },
+    /// A chart couldn't be drawn or encoded as an image.
+    Render {
+        path: PathBuf,
+        source: RenderError,
+    },
+    /// A log can't have more players than its player index fits, see
+    /// [`crate::marathon_log::MAX_PLAYERS`].
+    TooManyPlayers {
+        name: String,
+    },
+}
+
+/// Errors from drawing a chart as an image.
+#[derive(Debug)]
+pub enum RenderError {
+    /// The image would have no pixels.
+    EmptyImage,
+    /// The image couldn't be encoded as a PNG.
+    Encode(Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// Errors from playing an alert's tone.
+#[derive(Debug)]
+pub enum SoundError {
+    /// The tone couldn't be written to a sound file for the player.
+    WriteTone { path: PathBuf, source: io::Error },
+    /// The platform's sound player couldn't be started.
+    StartPlayer { player: String, source: io::Error },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockError {
+    /// The time since the timer was started doesn't fit in a u32 (about 136 years).
+    Overflow { elapsed_secs: u64 },
+}
+
+impl fmt::Display for TrackerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            TrackerError::Parse(err) => trf("invalid score: {0}", &[err]),
+            TrackerError::ImpossibleScore { score } => trf(
+                "invalid score: {0} isn't possible for this kind of entry",
+                &[score],
+            ),
+            TrackerError::Persist(err) => return err.fmt(f),
+            TrackerError::Clock(err) => trf("error getting time: {0}", &[err]),
+        };
+        f.write_str(&message)
+    }
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            PersistError::NotFound { path } => trf(
+                "{0} was not found, check the name and that it's in the tracker's folder",
+                &[&path.display()],
+            ),
+            PersistError::Io { path, source } => {
+                trf("could not access {0}: {1}", &[&path.display(), source])
+            }
+            PersistError::Deserialize {
+                path,
+                line,
+                col,
+                source,
+            } => trf(
+                "{0} is not a valid log (line {1}, column {2}): {3}",
+                &[&path.display(), line, col, source],
+            ),
+            PersistError::Serialize(source) => trf("log could not be serialized: {0}", &[source]),
+            PersistError::AllFileNamesTaken { first, last } => trf(
+                "{0} to {1} all exist, move old logs out of the folder",
+                &[&first.display(), &last.display()],
+            ),
+            PersistError::Render { path, source } => {
+                trf("could not draw {0}: {1}", &[&path.display(), source])
+            }
+            PersistError::TooManyPlayers { name } => trf(
+                "could not add player {0}, a log can't have more than {1} players",
+                &[name, &MAX_PLAYERS],
+            ),
+        };
+        f.write_str(&message)
+    }
+}
+
+impl fmt::Display for ClockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClockError::Overflow { elapsed_secs } => f.write_str(&trf(
+                "{0} seconds have passed, which is too long to track",
+                &[elapsed_secs],
+            )),
+        }
+    }
+}
+
+impl std::error::Error for TrackerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TrackerError::Parse(err) => Some(err),
+            TrackerError::ImpossibleScore { .. } => None,
+            TrackerError::Persist(err) => Some(err),
+            TrackerError::Clock(err) => Some(err),
+        }
+    }
+}
+
+impl std::error::Error for PersistError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PersistError::Io { source, .. } => Some(source),
+            PersistError::Deserialize { source, .. } => Some(source),
+            PersistError::Serialize(source) => Some(source),
+            PersistError::Render { source, .. } => Some(source),
+            PersistError::NotFound { .. }
+            | PersistError::AllFileNamesTaken { .. }
+            | PersistError::TooManyPlayers { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::EmptyImage => f.write_str(&tr("the image has no size")),
+            RenderError::Encode(err) => {
+                f.write_str(&trf("could not encode the image: {0}", &[err]))
+            }
+        }
+    }
+}
+
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RenderError::EmptyImage => None,
+            RenderError::Encode(err) => Some(err.as_ref()),
+        }
+    }
+}
+
+impl fmt::Display for SoundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            SoundError::WriteTone { path, source } => trf(
+                "could not write the alert tone to {0}: {1}",
+                &[&path.display(), source],
+            ),
+            SoundError::StartPlayer { player, source } => trf(
+                "could not start {0} to play the alert: {1}",
+                &[player, source],
+            ),
+        };
+        f.write_str(&message)
+    }
+}
+
+impl std::error::Error for SoundError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SoundError::WriteTone { source, .. } | SoundError::StartPlayer { source, .. } => {
+                Some(source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ClockError {}
+
+impl From<ParseError> for TrackerError {
+    fn from(err: ParseError) -> Self {
+        TrackerError::Parse(err)
+    }
+}
+
+impl From<PersistError> for TrackerError {
+    fn from(err: PersistError) -> Self {
+        TrackerError::Persist(err)
+    }
+}
+
+impl From<ClockError> for TrackerError {
+    fn from(err: ClockError) -> Self {
+        TrackerError::Clock(err)
+    }
+}
+
+impl PersistError {
+    pub fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        PersistError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+}
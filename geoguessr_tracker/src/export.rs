@@ -0,0 +1,116 @@
+//! Writing a log out as CSV, for spreadsheets and other tools.
+
+use std::fs;
+
+use crate::analysis::round_times;
+use crate::error::PersistError;
+use crate::marathon_log::{EntryTiming, MarathonLog};
+use crate::splits::SplitComparison;
+
+/// One row per entry, with the time each round took.
+pub fn entries_csv(marathon_log: &MarathonLog) -> String {
+    let mut csv = "entry,time_secs,score,kind,player,starts_game,round_secs,timing\n".to_owned();
+    for round in round_times(marathon_log.entries()) {
+        let entry = round.entry;
+        let row = [
+            (round.index + 1).to_string(),
+            entry.time_secs.to_string(),
+            entry.score.to_string(),
+            format!("{:?}", entry.kind),
+            csv_field(&marathon_log.player_name(entry.player)),
+            entry.starts_game.to_string(),
+            round.secs.to_string(),
+            timing_field(entry.timing).to_owned(),
+        ];
+        csv += &row.join(",");
+        csv.push('\n');
+    }
+    csv
+}
+
+/// The timing column, with whether grace entries counted spelled out.
+fn timing_field(timing: EntryTiming) -> &'static str {
+    match timing {
+        EntryTiming::OnTime => "OnTime",
+        EntryTiming::Grace { counted: true } => "Grace",
+        EntryTiming::Grace { counted: false } => "GraceNotCounted",
+        EntryTiming::Late => "Late",
+    }
+}
+
+/// One row per split, with the comparisons left empty where there's nothing to compare with.
+pub fn splits_csv(comparisons: &[SplitComparison]) -> String {
+    let mut csv = "start_secs,end_secs,count,cumulative_count,rounds,hits,hit_rate,mean_score,\
+        target_cumulative,vs_target,previous_cumulative,vs_previous\n"
+        .to_owned();
+    for comparison in comparisons {
+        let split = &comparison.split;
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        let row = [
+            split.start_secs.to_string(),
+            split.end_secs.to_string(),
+            split.count.to_string(),
+            split.cumulative_count.to_string(),
+            split.rounds.to_string(),
+            split.hits.to_string(),
+            optional(split.hit_rate().map(|rate| format!("{rate:.3}"))),
+            optional(split.mean_score().map(|mean| format!("{mean:.1}"))),
+            optional(comparison.target_cumulative.map(|t| format!("{t:.1}"))),
+            optional(comparison.vs_target().map(|diff| format!("{diff:.1}"))),
+            optional(comparison.previous.map(|p| p.cumulative_count.to_string())),
+            optional(comparison.vs_previous().map(|diff| diff.to_string())),
+        ];
+        csv += &row.join(",");
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Writes `csv` to `path`, replacing the file if it exists.
+pub fn write_csv(path: &str, csv: &str) -> Result<(), PersistError> {
+    fs::write(path, csv).map_err(|err| PersistError::io(path, err))
+}
+
+/// Quotes `field` if it has characters that would break the row up.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marathon_log::{Entry, EntryKind};
+    use crate::splits::{compare_splits, compute_splits};
+
+    #[test]
+    fn csv_test() {
+        let entries = [(5000, 30), (1200, 70)].map(|(score, time_secs)| Entry {
+            score,
+            time_secs,
+            kind: EntryKind::Guess,
+            player: 0,
+            starts_game: false,
+            timing: EntryTiming::OnTime,
+        });
+        let mut marathon_log = MarathonLog::from_entries(100, entries);
+        marathon_log.set_player_names(vec!["Smith, \"Ana\"".to_owned()]);
+        let csv = entries_csv(&marathon_log);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[2],
+            "2,70,1200,Guess,\"Smith, \"\"Ana\"\"\",false,40,OnTime"
+        );
+
+        let rule = marathon_log.scoring_rule();
+        let splits = compute_splits(marathon_log.entries(), &rule, 50, 100);
+        let csv = splits_csv(&compare_splits(&splits, Some(4), 100, &[]));
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[1], "0,50,1,1,1,1,1.000,5000.0,2.0,-1.0,,");
+        assert_eq!(lines[0].split(',').count(), lines[2].split(',').count());
+    }
+}
@@ -0,0 +1,155 @@
+//! Grouping the rounds of a log into games of five rounds.
+//!
+//! A new game starts at an entry marked with [`Entry::starts_game`], or otherwise after five
+//! rounds. Skipped games are entries of their own, so they end the game before them but aren't
+//! part of any game.
+
+use crate::marathon_log::{Entry, EntryKind};
+use crate::scoring::ScoringRule;
+
+pub const ROUNDS_PER_GAME: usize = 5;
+
+/// The rounds of one game, which has fewer than five rounds if it's still going or was cut short.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    pub rounds: Vec<Entry>,
+}
+
+impl Game {
+    pub fn total_score(&self) -> u32 {
+        self.rounds.iter().map(|round| u32::from(round.score)).sum()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.rounds.len() == ROUNDS_PER_GAME
+    }
+
+    /// Whether every round of a complete game was a 5k.
+    pub fn is_25k(&self) -> bool {
+        self.is_complete() && self.rounds.iter().all(|round| round.score == 5000)
+    }
+}
+
+/// How a round position (first round, second round, ...) went across every game.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RoundPositionStats {
+    pub rounds: u32,
+    /// Rounds that were hits under the log's scoring rule.
+    pub hits: u32,
+    pub total_points: u64,
+}
+
+impl RoundPositionStats {
+    pub fn mean_score(&self) -> Option<f64> {
+        (self.rounds > 0).then(|| self.total_points as f64 / f64::from(self.rounds))
+    }
+
+    pub fn hit_rate(&self) -> Option<f64> {
+        (self.rounds > 0).then(|| f64::from(self.hits) / f64::from(self.rounds))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GameStats {
+    pub games: u32,
+    pub complete_games: u32,
+    pub skipped_games: u32,
+    pub twenty_five_ks: u32,
+    /// The highest total of a complete game.
+    pub best_total: Option<u32>,
+    /// Stats for each round position, the first round at index 0.
+    pub positions: [RoundPositionStats; ROUNDS_PER_GAME],
+}
+
+/// Groups `entries`, which should be in time order, into games.
+pub fn group_games(entries: impl IntoIterator<Item = Entry>) -> Vec<Game> {
+    let mut games: Vec<Game> = Vec::new();
+    let mut in_game = false;
+    for entry in entries {
+        if entry.kind == EntryKind::Skipped {
+            in_game = false;
+            continue;
+        }
+        match games.last_mut() {
+            Some(game) if in_game && !entry.starts_game && !game.is_complete() => {
+                game.rounds.push(entry);
+            }
+            _ => games.push(Game {
+                rounds: vec![entry],
+            }),
+        }
+        in_game = true;
+    }
+    games
+}
+
+/// Adds up the games in `entries`, counting hits under `rule`.
+pub fn game_stats(entries: impl IntoIterator<Item = Entry>, rule: &ScoringRule) -> GameStats {
+    let entries: Vec<Entry> = entries.into_iter().collect();
+    let mut stats = GameStats {
+        skipped_games: entries
+            .iter()
+            .filter(|entry| entry.kind == EntryKind::Skipped)
+            .count() as u32,
+        ..GameStats::default()
+    };
+    for game in group_games(entries) {
+        stats.games += 1;
+        if game.is_complete() {
+            stats.complete_games += 1;
+            stats.best_total = stats.best_total.max(Some(game.total_score()));
+        }
+        stats.twenty_five_ks += u32::from(game.is_25k());
+        for (position, round) in game.rounds.iter().enumerate() {
+            let position_stats = &mut stats.positions[position];
+            position_stats.rounds += 1;
+            position_stats.hits += u32::from(rule.is_hit(round));
+            position_stats.total_points += u64::from(round.score);
+        }
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marathon_log::EntryTiming;
+
+    fn round(score: u16, starts_game: bool) -> Entry {
+        Entry {
+            score,
+            time_secs: 0,
+            kind: EntryKind::Guess,
+            player: 0,
+            starts_game,
+            timing: EntryTiming::OnTime,
+        }
+    }
+
+    #[test]
+    fn games_test() {
+        let skip = Entry {
+            kind: EntryKind::Skipped,
+            ..round(0, false)
+        };
+        let mut entries = vec![round(5000, false); 7];
+        entries.push(skip);
+        entries.extend([round(4000, false), round(5000, false)]);
+        entries.extend([round(5000, true), round(3000, false)]);
+
+        let games = group_games(entries.iter().copied());
+        let lengths: Vec<usize> = games.iter().map(|game| game.rounds.len()).collect();
+        assert_eq!(lengths, [5, 2, 2, 2]);
+        assert!(games[0].is_25k());
+        assert_eq!(games[3].total_score(), 8000);
+
+        let stats = game_stats(entries, &ScoringRule::default());
+        assert_eq!((stats.games, stats.complete_games), (4, 1));
+        assert_eq!((stats.skipped_games, stats.twenty_five_ks), (1, 1));
+        assert_eq!(stats.best_total, Some(25_000));
+        assert_eq!(stats.positions[0].rounds, 4);
+        assert_eq!(stats.positions[0].hits, 3);
+        assert_eq!(stats.positions[1].mean_score(), Some(4500.0));
+        assert_eq!(stats.positions[4].hit_rate(), Some(1.0));
+    }
+}
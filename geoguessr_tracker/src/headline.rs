@@ -0,0 +1,187 @@
+//! The headline numbers of a finished run, for the summary screen and the HTML report.
+
+use crate::analysis::{analyze_timing, droughts, Drought, DEFAULT_BUCKET_SECS};
+use crate::i18n::{fmt_decimal, fmt_int, fmt_percent, tr, trf};
+use crate::marathon_log::{EntryKind, EntryTiming, MarathonLog};
+use crate::scoring::ScoringRule;
+use crate::utils::time_counter;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeadlineStats {
+    /// The count under the scoring rule, e.g. the number of 5ks.
+    pub count: u32,
+    /// Rounds played, not counting skipped games, or entries that don't add to the count.
+    pub rounds: u32,
+    /// Rounds that were hits under the scoring rule.
+    pub hits: u32,
+    pub total_points: u64,
+    pub twenty_five_ks: u32,
+    /// The highest total of a complete game.
+    pub best_game: Option<u32>,
+    pub mean_round_secs: Option<f64>,
+    /// The longest drought, the earliest one if several are as long.
+    pub longest_drought: Option<Drought>,
+    /// Real time spent paused.
+    pub paused_secs: u32,
+    pub grace_entries: u32,
+    /// Grace entries that add to the count.
+    pub grace_counted: u32,
+    pub late_entries: u32,
+}
+
+impl HeadlineStats {
+    pub fn hit_rate(&self) -> Option<f64> {
+        (self.rounds > 0).then(|| f64::from(self.hits) / f64::from(self.rounds))
+    }
+
+    pub fn mean_score(&self) -> Option<f64> {
+        (self.rounds > 0).then(|| self.total_points as f64 / f64::from(self.rounds))
+    }
+
+    /// The numbers as (name, value) rows in the current language, as both the summary screen and
+    /// the report show them. Grace and late entries are only listed if there were any.
+    pub fn rows(&self, rule: &ScoringRule) -> Vec<(String, String)> {
+        let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_owned());
+        let mut rows = vec![
+            (rule.count_label(), fmt_int(self.count)),
+            (tr("Rounds"), fmt_int(self.rounds)),
+            (
+                trf("{0} rate", &[&rule.hit_name()]),
+                or_dash(self.hit_rate().map(fmt_percent)),
+            ),
+            (
+                tr("Average"),
+                or_dash(self.mean_score().map(|mean| fmt_decimal(mean, 1))),
+            ),
+            (tr("25ks"), fmt_int(self.twenty_five_ks)),
+            (tr("Best game"), or_dash(self.best_game.map(fmt_int))),
+            (
+                tr("Mean round"),
+                or_dash(
+                    self.mean_round_secs
+                        .map(|secs| trf("{0}s", &[&fmt_decimal(secs, 1)])),
+                ),
+            ),
+            (
+                tr("Longest drought"),
+                or_dash(self.longest_drought.map(|d| time_counter(d.secs()))),
+            ),
+            (tr("Time paused"), time_counter(self.paused_secs)),
+        ];
+        if self.grace_entries > 0 {
+            rows.push((
+                tr("Grace entries"),
+                trf(
+                    "{0} ({1} counted)",
+                    &[&fmt_int(self.grace_entries), &fmt_int(self.grace_counted)],
+                ),
+            ));
+        }
+        if self.late_entries > 0 {
+            rows.push((tr("Late entries"), fmt_int(self.late_entries)));
+        }
+        rows
+    }
+}
+
+pub fn headline_stats(marathon_log: &MarathonLog) -> HeadlineStats {
+    let rule = marathon_log.scoring_rule();
+    let played: Vec<_> = marathon_log
+        .entries()
+        .filter(|entry| entry.kind != EntryKind::Skipped && entry.timing.counts())
+        .collect();
+    let game_stats = marathon_log.game_stats();
+    let timing = analyze_timing(marathon_log.entries(), &rule, DEFAULT_BUCKET_SECS, 0);
+    let longest_drought = droughts(marathon_log.entries(), &rule, marathon_log.played_secs())
+        .into_iter()
+        .reduce(|longest, drought| match drought.secs() > longest.secs() {
+            true => drought,
+            false => longest,
+        });
+    let timings: Vec<EntryTiming> = marathon_log.entries().map(|entry| entry.timing).collect();
+    let count_timings = |matches: fn(&EntryTiming) -> bool| {
+        timings.iter().filter(|timing| matches(timing)).count() as u32
+    };
+
+    HeadlineStats {
+        count: marathon_log.count(),
+        rounds: played.len() as u32,
+        hits: played.iter().filter(|entry| rule.is_hit(entry)).count() as u32,
+        total_points: played.iter().map(|entry| u64::from(entry.score)).sum(),
+        twenty_five_ks: game_stats.twenty_five_ks,
+        best_game: game_stats.best_total,
+        mean_round_secs: timing.mean_secs,
+        longest_drought,
+        paused_secs: marathon_log
+            .pauses()
+            .iter()
+            .map(|pause| pause.real_secs)
+            .sum(),
+        grace_entries: count_timings(|timing| matches!(timing, EntryTiming::Grace { .. })),
+        grace_counted: count_timings(|timing| *timing == EntryTiming::Grace { counted: true }),
+        late_entries: count_timings(|timing| *timing == EntryTiming::Late),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marathon_log::{Entry, Pause};
+
+    #[test]
+    fn headline_stats_test() {
+        let entries = [
+            (5000, 30, EntryKind::Guess, EntryTiming::OnTime),
+            (1200, 70, EntryKind::Guess, EntryTiming::OnTime),
+            (0, 80, EntryKind::Skipped, EntryTiming::OnTime),
+            (5000, 500, EntryKind::Guess, EntryTiming::OnTime),
+            (
+                5000,
+                1000,
+                EntryKind::Guess,
+                EntryTiming::Grace { counted: true },
+            ),
+            (
+                5000,
+                1000,
+                EntryKind::Guess,
+                EntryTiming::Grace { counted: false },
+            ),
+            (5000, 1000, EntryKind::Guess, EntryTiming::Late),
+        ]
+        .map(|(score, time_secs, kind, timing)| Entry {
+            score,
+            time_secs,
+            kind,
+            player: 0,
+            starts_game: false,
+            timing,
+        });
+        let mut marathon_log = MarathonLog::from_entries(1000, entries);
+        marathon_log.insert_pause(Pause {
+            time_secs: 100,
+            real_secs: 600,
+        });
+
+        let stats = headline_stats(&marathon_log);
+        assert_eq!(stats.count, 3);
+        assert_eq!(
+            (stats.rounds, stats.hits, stats.total_points),
+            (4, 3, 16_200)
+        );
+        assert_eq!(stats.hit_rate(), Some(0.75));
+        assert_eq!(stats.mean_score(), Some(4050.0));
+        let drought = stats.longest_drought.unwrap();
+        assert_eq!((drought.start_secs, drought.end_secs), (500, 1000));
+        assert_eq!(stats.paused_secs, 600);
+        assert_eq!(
+            (stats.grace_entries, stats.grace_counted, stats.late_entries),
+            (2, 1, 1)
+        );
+
+        let rows = stats.rows(&marathon_log.scoring_rule());
+        assert_eq!(rows[0], ("5k count".to_owned(), "3".to_owned()));
+        assert!(rows.contains(&("5k rate".to_owned(), "75.0%".to_owned())));
+        assert!(rows.contains(&("Grace entries".to_owned(), "2 (1 counted)".to_owned())));
+    }
+}
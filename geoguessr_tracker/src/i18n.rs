@@ -0,0 +1,253 @@
+//! Translations of the tracker's text, and numbers formatted the way each language writes them.
+//!
+//! Text is looked up by its English wording, so anything without a translation stays in English.
+//! The French and Portuguese translations are built in, and a file in the lang folder named after
+//! the language code, e.g. lang/fr.ron, adds to or replaces them without rebuilding.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::PersistError;
+
+/// Translation files are read from this folder in the tracker's folder.
+pub const LANG_DIR: &str = "lang";
+
+const FRENCH: &str = include_str!("lang/fr.ron");
+const PORTUGUESE: &str = include_str!("lang/pt.ron");
+
+/// The language in use and its translations, English with no translations until
+/// [`set_language`] is called.
+static CURRENT: RwLock<Option<Translations>> = RwLock::new(None);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    French,
+    Portuguese,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::English, Language::French, Language::Portuguese];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::French => "fr",
+            Language::Portuguese => "pt",
+        }
+    }
+
+    /// The built-in translations, as RON.
+    fn built_in(self) -> &'static str {
+        match self {
+            Language::English => "{}",
+            Language::French => FRENCH,
+            Language::Portuguese => PORTUGUESE,
+        }
+    }
+
+    /// The separators for thousands and decimals.
+    fn separators(self) -> (&'static str, &'static str) {
+        match self {
+            Language::English => (",", "."),
+            // a narrow no-break space, so numbers don't wrap
+            Language::French => ("\u{202f}", ","),
+            Language::Portuguese => (".", ","),
+        }
+    }
+}
+
+/// Each language is named in itself, so it can be found without reading the current one.
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Language::English => "English",
+            Language::French => "Français",
+            Language::Portuguese => "Português",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Translations {
+    pub language: Language,
+    /// The translations, by their English text.
+    pub strings: HashMap<String, String>,
+}
+
+impl Translations {
+    /// The built-in translations for `language`, with the ones in its file in the lang folder
+    /// on top. If the file can't be read the built-in ones are used, and the error is returned
+    /// with them.
+    pub fn load(language: Language) -> (Self, Option<PersistError>) {
+        let mut strings: HashMap<String, String> =
+            ron::from_str(language.built_in()).expect("built-in translations should parse");
+        let path = format!("{LANG_DIR}/{}.ron", language.code());
+        let file_err = match read_translation_file(&path) {
+            Ok(file_strings) => {
+                strings.extend(file_strings);
+                None
+            }
+            Err(PersistError::NotFound { .. }) => None,
+            Err(err) => Some(err),
+        };
+        (Translations { language, strings }, file_err)
+    }
+
+    pub fn get<'a>(&'a self, text: &'a str) -> &'a str {
+        self.strings.get(text).map_or(text, String::as_str)
+    }
+}
+
+fn read_translation_file(path: &str) -> Result<HashMap<String, String>, PersistError> {
+    if !fs::exists(path).map_err(|err| PersistError::io(path, err))? {
+        return Err(PersistError::NotFound { path: path.into() });
+    }
+    let contents = fs::read_to_string(path).map_err(|err| PersistError::io(path, err))?;
+    ron::from_str(&contents).map_err(|err| PersistError::Deserialize {
+        path: path.into(),
+        line: err.position.line,
+        col: err.position.col,
+        source: err.code,
+    })
+}
+
+/// Switches the text and numbers to `language`. Returns the error if its translation file
+/// couldn't be read, in which case the built-in translations are used.
+pub fn set_language(language: Language) -> Result<(), PersistError> {
+    let (translations, file_err) = Translations::load(language);
+    *CURRENT.write().unwrap_or_else(|err| err.into_inner()) = Some(translations);
+    file_err.map_or(Ok(()), Err)
+}
+
+pub fn language() -> Language {
+    let current = CURRENT.read().unwrap_or_else(|err| err.into_inner());
+    current.as_ref().map_or(Language::default(), |t| t.language)
+}
+
+/// `text` in the current language.
+pub fn tr(text: &str) -> String {
+    let current = CURRENT.read().unwrap_or_else(|err| err.into_inner());
+    match current.as_ref() {
+        Some(translations) => translations.get(text).to_owned(),
+        None => text.to_owned(),
+    }
+}
+
+/// `text` in the current language, with `{0}`, `{1}`, ... replaced by `args`. The placeholders
+/// are numbered so translations can put them in a different order.
+pub fn trf(text: &str, args: &[&dyn fmt::Display]) -> String {
+    fill_placeholders(&tr(text), args)
+}
+
+fn fill_placeholders(template: &str, args: &[&dyn fmt::Display]) -> String {
+    let mut filled = template.to_owned();
+    for (i, arg) in args.iter().enumerate() {
+        filled = filled.replace(&format!("{{{i}}}"), &arg.to_string());
+    }
+    filled
+}
+
+/// A whole number with the current language's thousands separators, e.g. 12,345 or 12 345.
+pub fn fmt_int(n: impl Into<u64>) -> String {
+    fmt_int_in(language(), n.into())
+}
+
+/// A number with `decimals` places, written the current language's way.
+pub fn fmt_decimal(x: f64, decimals: usize) -> String {
+    fmt_decimal_in(language(), x, decimals)
+}
+
+/// A fraction as a percentage with one decimal, e.g. 12.5% or 12,5 %.
+pub fn fmt_percent(fraction: f64) -> String {
+    let language = language();
+    let number = fmt_decimal_in(language, fraction * 100.0, 1);
+    match language {
+        Language::French => format!("{number}\u{a0}%"),
+        Language::English | Language::Portuguese => format!("{number}%"),
+    }
+}
+
+fn fmt_int_in(language: Language, n: u64) -> String {
+    let (thousands, _) = language.separators();
+    let digits = n.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3 * 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped += thousands;
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+fn fmt_decimal_in(language: Language, x: f64, decimals: usize) -> String {
+    let (_, decimal) = language.separators();
+    let fixed = format!("{:.*}", decimals, x.abs());
+    let (whole, fraction) = fixed.split_once('.').unwrap_or((&fixed, ""));
+    let sign = if x < 0.0 && fixed.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        "-"
+    } else {
+        ""
+    };
+    let whole = fmt_int_in(language, whole.parse().unwrap_or(0));
+    match fraction {
+        "" => format!("{sign}{whole}"),
+        fraction => format!("{sign}{whole}{decimal}{fraction}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The numbered placeholders in `text`, sorted.
+    fn placeholders(text: &str) -> Vec<String> {
+        let mut found: Vec<String> = (0..10)
+            .map(|i| format!("{{{i}}}"))
+            .filter(|placeholder| text.contains(placeholder.as_str()))
+            .collect();
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn i18n_test() {
+        assert_eq!(fmt_int_in(Language::English, 1234567), "1,234,567");
+        assert_eq!(fmt_int_in(Language::French, 1234), "1\u{202f}234");
+        assert_eq!(fmt_int_in(Language::Portuguese, 999), "999");
+        assert_eq!(fmt_decimal_in(Language::Portuguese, 1234.56, 1), "1.234,6");
+        assert_eq!(fmt_decimal_in(Language::English, -0.04, 1), "0.0");
+        assert_eq!(fmt_decimal_in(Language::English, -2.6, 0), "-3");
+        assert_eq!(
+            fill_placeholders("{1} de {0}", &[&"a", &2]),
+            "2 de a".to_owned()
+        );
+
+        // every translation parses, and keeps the placeholders of the English text
+        for language in Language::ALL {
+            let strings: HashMap<String, String> = ron::from_str(language.built_in()).unwrap();
+            for (english, translated) in &strings {
+                assert_eq!(
+                    placeholders(english),
+                    placeholders(translated),
+                    "{english} in {language}"
+                );
+            }
+        }
+        let (french, _) = Translations::load(Language::French);
+        assert_eq!(french.get("Time left:"), "Temps restant :");
+        assert_eq!(french.get("not translated"), "not translated");
+        // numbers are formatted into the fixed part, so any threshold finds its translation
+        assert_eq!(
+            fill_placeholders(french.get("{0} streak"), &[&"4900+"]),
+            "Série de 4900+"
+        );
+    }
+}
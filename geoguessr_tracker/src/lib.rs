@@ -0,0 +1,42 @@
+//! Tracking for GeoGuessr marathons, where the goal is to get as many 5ks (perfect rounds) as
+//! possible within a set time, usually 24 hours.
+//!
+//! [`marathon_log::MarathonLog`] holds the timer and entries of a marathon and can be saved to and
+//! loaded from RON files. [`parsing`] turns text copied from the round result screen into scores,
+//! [`replay`] plays back a saved log, and [`utils`] has the time formatting used for display.
+//!
+//! ```
+//! use geoguessr_tracker::marathon_log::MarathonLog;
+//! use geoguessr_tracker::parsing::score_from_str;
+//! use geoguessr_tracker::utils::timekeeping::TWENTY_FOUR_HOURS_IN_SECS;
+//!
+//! let mut marathon_log = MarathonLog::new(TWENTY_FOUR_HOURS_IN_SECS);
+//! marathon_log.resume();
+//! marathon_log.try_add_entry(score_from_str("5,000 points").unwrap());
+//! assert_eq!(marathon_log.count(), 1);
+//! ```
+
+pub mod alerts;
+pub mod analysis;
+pub mod charts;
+pub mod clock;
+pub mod error;
+pub mod export;
+pub mod games;
+pub mod headline;
+pub mod i18n;
+pub mod library;
+pub mod marathon_log;
+pub mod merge;
+pub mod metadata;
+pub mod parsing;
+pub mod replay;
+pub mod report;
+pub mod scoring;
+pub mod settings;
+pub mod splits;
+pub mod sync;
+#[cfg(any(test, feature = "synthetic"))]
+pub mod synthetic;
+pub mod theme;
+pub mod utils;
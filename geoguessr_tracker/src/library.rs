@@ -0,0 +1,102 @@
+//! The saved runs in a folder, with their metadata, for finding an old run again.
+
+use std::fs;
+use std::path::Path;
+
+use log::warn;
+
+use crate::marathon_log::MarathonLog;
+use crate::metadata::RunMetadata;
+use crate::scoring::ScoringRule;
+
+/// A saved run, as listed in the library.
+#[derive(Debug, Clone)]
+pub struct RunSummary {
+    pub path: String,
+    pub metadata: RunMetadata,
+    pub entry_count: usize,
+    pub count: u32,
+    pub scoring_rule: ScoringRule,
+}
+
+/// Reads every `.ron` file in `dir` that is a saved log, sorted by start time with the newest run
+/// first. Files that can't be loaded are left out, and files that don't have the length of the
+/// marathon saved are taken to last `duration_secs`.
+pub fn scan_runs(dir: impl AsRef<Path>, duration_secs: u32) -> std::io::Result<Vec<RunSummary>> {
+    let mut runs = Vec::new();
+    for dir_entry in fs::read_dir(dir)? {
+        let path = dir_entry?.path();
+        if path.extension().is_none_or(|ext| ext != "ron") {
+            continue;
+        }
+        let Some(path) = path.to_str() else {
+            continue;
+        };
+        match MarathonLog::from_file(duration_secs, path) {
+            Ok(marathon_log) => runs.push(RunSummary {
+                path: path.to_owned(),
+                metadata: marathon_log.metadata().clone(),
+                entry_count: marathon_log.entries().count(),
+                count: marathon_log.count(),
+                scoring_rule: marathon_log.scoring_rule(),
+            }),
+            Err(err) => warn!("skipping {path} in run library: {err}"),
+        }
+    }
+    runs.sort_by(|a, b| {
+        let newest_first = b
+            .metadata
+            .started_at_unix_secs
+            .cmp(&a.metadata.started_at_unix_secs);
+        newest_first.then_with(|| a.path.cmp(&b.path))
+    });
+    Ok(runs)
+}
+
+/// The runs whose metadata or file name contain `query`, ignoring case.
+pub fn search<'a>(runs: &'a [RunSummary], query: &'a str) -> impl Iterator<Item = &'a RunSummary> {
+    let query_lower = query.trim().to_lowercase();
+    runs.iter().filter(move |run| {
+        run.metadata.matches(query) || run.path.to_lowercase().contains(&query_lower)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::GameMode;
+    use crate::utils::timekeeping::TWENTY_FOUR_HOURS_IN_SECS;
+
+    #[test]
+    fn library_test() {
+        let dir = std::env::temp_dir().join("geoguessr_tracker_library_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+
+        for (name, map_name, started_at) in [("a.ron", "World", 100), ("b.ron", "Europe", 200)] {
+            let mut marathon_log = MarathonLog::new(TWENTY_FOUR_HOURS_IN_SECS);
+            *marathon_log.metadata_mut() = RunMetadata {
+                map_name: map_name.to_owned(),
+                game_mode: GameMode::NoMove,
+                started_at_unix_secs: Some(started_at),
+                ..Default::default()
+            };
+            let path = dir.join(name);
+            marathon_log.save_to_path(path.to_str().unwrap()).unwrap();
+        }
+        fs::write(dir.join("settings.ron"), "(not_a_log: true)").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let runs = scan_runs(&dir, TWENTY_FOUR_HOURS_IN_SECS);
+        fs::remove_dir_all(&dir).unwrap();
+        let runs = runs.unwrap();
+        let maps: Vec<&str> = runs
+            .iter()
+            .map(|run| run.metadata.map_name.as_str())
+            .collect();
+        assert_eq!(maps, ["Europe", "World"]);
+        assert_eq!(search(&runs, "world").count(), 1);
+        assert_eq!(search(&runs, "NM").count(), 2);
+        assert_eq!(search(&runs, "b.ron").count(), 1);
+    }
+}
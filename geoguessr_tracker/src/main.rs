@@ -1,33 +1,68 @@
 use std::default::Default;
-use std::time::Instant;
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 
 use env_logger::{Builder, Env};
 #[allow(unused_imports)]
 use log::{debug, error, info, warn};
 
 use eframe::egui::{
-    self, Button, FontData, FontDefinitions, FontFamily, FontId, RichText, Style, TextStyle::*, Ui,
-    Vec2,
+    self, Button, Response, RichText, Slider, Ui, WidgetInfo, WidgetText, WidgetType,
 };
 use eframe::NativeOptions;
 
-mod marathon_log;
-mod utils;
-
-use marathon_log::{AddEntryResult, MarathonLog};
-use utils::time_counter;
-use utils::timekeeping::HoursMinutesSeconds;
-use utils::{calculate_countdown, score_from_str, timekeeping::TWENTY_FOUR_HOURS_IN_SECS};
+use geoguessr_tracker::alerts::{play_alert_sound, Alert, AlertTracker};
+use geoguessr_tracker::analysis::{
+    analyze_timing, DroughtLevel, DroughtThresholds, DEFAULT_BUCKET_SECS,
+};
+use geoguessr_tracker::charts::save_charts;
+use geoguessr_tracker::error::{ClockError, PersistError, TrackerError};
+use geoguessr_tracker::export::{entries_csv, splits_csv, write_csv};
+use geoguessr_tracker::games::ROUNDS_PER_GAME;
+use geoguessr_tracker::headline::headline_stats;
+use geoguessr_tracker::i18n::{fmt_decimal, fmt_int, fmt_percent, set_language, tr, trf, Language};
+use geoguessr_tracker::library::{scan_runs, search, RunSummary};
+use geoguessr_tracker::marathon_log::{
+    AddEntryResult, Entry, GraceRule, LateEntryPolicy, MarathonLog, PausePolicy, Snapshot,
+};
+use geoguessr_tracker::merge::{merge_files, split_log, MergeOptions};
+use geoguessr_tracker::metadata::{GameMode, RunMetadata};
+use geoguessr_tracker::parsing::{
+    is_new_game_command, parse_round_result, score_from_str, RoundResult,
+};
+use geoguessr_tracker::replay::{Replay, MAX_SPEED, MIN_SPEED};
+use geoguessr_tracker::report::{html_report, write_report};
+use geoguessr_tracker::scoring::ScoringRule;
+use geoguessr_tracker::settings::{Settings, SETTINGS_FILE};
+use geoguessr_tracker::splits::{log_splits, HOUR_SECS};
+use geoguessr_tracker::sync::{SyncClient, SyncHost};
+use geoguessr_tracker::theme::{theme_files, Theme, ThemePreset, ThemeSource};
+use geoguessr_tracker::utils::timekeeping::HoursMinutesSeconds;
+use geoguessr_tracker::utils::{spoken_duration, time_counter};
 
+mod cli;
+mod theme_style;
 
 const APP_NAME: &str = "GeoMarathonTracker";
+/// How many of the slowest rounds the review screen lists.
+const REVIEW_SLOWEST_COUNT: usize = 10;
+/// How long an alert stays on screen.
+const FLASH_DURATION: Duration = Duration::from_secs(8);
 
 fn main() {
     let env = Env::new().default_filter_or("INFO");
     let mut env_logger_builder = Builder::from_env(env);
     env_logger_builder.init();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(msg) = cli::run(&args) {
+            eprintln!("{msg}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let eframe_opts = custom_native_opts(NativeOptions::default());
     let start_res = eframe::run_native(
         APP_NAME,
@@ -39,9 +74,30 @@ fn main() {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Screen {
+    Start,
+    Tracker,
+    Replay,
+    /// Stats and details of the loaded run, going back to `return_to`.
+    Review {
+        return_to: ReviewReturn,
+    },
+    /// The final numbers, shown when the marathon ends.
+    Summary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReviewReturn {
+    Start,
+    Tracker,
+    Summary,
+}
+
 struct EguiTrackerApp {
-    is_started: bool,
+    screen: Screen,
     marathon_log: MarathonLog,
+    replay: Option<Replay>,
     save_on_exit: bool,
     score_input_txt: String,
     file_name_txt: String,
@@ -49,17 +105,51 @@ struct EguiTrackerApp {
     mins_txt: String,
     secs_txt: String,
     headstart_5k_txt: String,
+    players_txt: String,
+    sync_addr_txt: String,
+    team_sync: TeamSync,
+    merge_files_txt: String,
+    merge_rebase: bool,
+    split_at_txt: String,
+    merge_report_txt: String,
+    library_runs: Vec<RunSummary>,
+    library_query_txt: String,
+    split_mins: u32,
+    compare_file_txt: String,
+    compare_log: Option<MarathonLog>,
+    settings: Settings,
+    duration_txt: String,
+    grace_txt: String,
+    settings_status_txt: String,
+    /// Whether the end of the marathon has been handled, so it's only done once.
+    marathon_ended: bool,
+    auto_save_txt: String,
+    alert_tracker: AlertTracker,
+    /// The alerts on screen, with when they fired.
+    flashes: Vec<(Alert, Instant)>,
+    theme: Theme,
+    /// The files in the themes folder, found when the tracker starts or a theme is saved.
+    theme_files: Vec<String>,
     err_state: AppErrState,
 }
 
+/// Whether this tracker shares entries with other trackers.
+enum TeamSync {
+    Off,
+    Host(SyncHost),
+    Client(SyncClient),
+}
+
 impl eframe::App for EguiTrackerApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         egui::CentralPanel::default().show(ctx, |ui| {
-            if self.is_started {
-                self.show_tracker_display(ui);
-            } else {
-                self.show_start_display(ui);
-            }
+            egui::ScrollArea::vertical().show(ui, |ui| match self.screen {
+                Screen::Start => self.show_start_display(ui),
+                Screen::Tracker => self.show_tracker_display(ui),
+                Screen::Replay => self.show_replay_display(ui),
+                Screen::Review { return_to } => self.show_review_display(ui, return_to),
+                Screen::Summary => self.show_summary_display(ui),
+            });
         });
 
         ctx.request_repaint();
@@ -67,9 +157,8 @@ impl eframe::App for EguiTrackerApp {
 
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
         if self.save_on_exit {
-            let res = self.marathon_log.save_to_file();
-            if res.is_err() {
-                error!("error saving to file: {}", res.unwrap_err().to_string());
+            if let Err(err) = self.marathon_log.save_to_file() {
+                error!("error saving to file: {}", err.to_string());
             }
         }
     }
@@ -79,167 +168,1201 @@ impl EguiTrackerApp {
     fn show_start_display(&mut self, ui: &mut Ui) {
         use egui::TextEdit;
 
-        if ui.button("Start timer").clicked() {
-            self.is_started = true;
-            if let Ok(hms) = HoursMinutesSeconds::from_strs(&self.hours_txt, &self.mins_txt, &self.secs_txt) {
-                let total_secs = TWENTY_FOUR_HOURS_IN_SECS - hms.total_secs();
-                self.marathon_log.epoch_offset_secs = total_secs;
-            }
-            self.marathon_log.current_epoch = Some(Instant::now());
-            if let Ok(count) = u16::from_str(&self.headstart_5k_txt) {
-                let added_up = self.marathon_log.add_up_5ks();
-                if added_up != count {
-                    if added_up > count {
-                        error!("data from file has more 5ks than provided number");
-                    }
-                    if added_up < count {
-                        let diff = count - added_up;
-                        for _ in 0..diff {
-                            self.marathon_log.try_add_entry(5000);
-                        }
-                    }
+        if ui.button(tr("Start timer")).clicked() {
+            self.screen = Screen::Tracker;
+            self.apply_settings();
+            self.alert_tracker = AlertTracker::default();
+            self.flashes.clear();
+            if let Ok(hms) =
+                HoursMinutesSeconds::from_strs(&self.hours_txt, &self.mins_txt, &self.secs_txt)
+            {
+                let total_secs = self
+                    .marathon_log
+                    .duration_secs()
+                    .saturating_sub(hms.total_secs());
+                self.marathon_log.set_elapsed_secs(total_secs);
+            }
+            let player_names: Vec<String> = self
+                .players_txt
+                .split(',')
+                .map(|name| name.trim().to_owned())
+                .filter(|name| !name.is_empty())
+                .collect();
+            if !player_names.is_empty() {
+                self.marathon_log.set_player_names(player_names);
+            }
+            self.marathon_log.metadata_mut().set_started_now();
+            self.marathon_log.resume();
+            if let Ok(count) = u32::from_str(&self.headstart_5k_txt) {
+                if self.marathon_log.add_up_count() > count {
+                    error!("data from file has a higher count than provided number");
                 }
-                self.marathon_log.total_5ks = self.marathon_log.add_up_5ks();
+                self.marathon_log.add_headstart(count);
             }
         }
         let save_btn_txt = if self.save_on_exit {
-            "saving on exit is ON"
+            tr("saving on exit is ON")
         } else {
-            "saving on exit is OFF"
+            tr("saving on exit is OFF")
         };
         ui.label("");
         if ui.button(save_btn_txt).clicked() {
             self.save_on_exit = !self.save_on_exit;
         }
 
-        ui.heading("File name:");
+        ui.heading(tr("File name:"));
         ui.add(TextEdit::singleline(&mut self.file_name_txt));
-        if ui.button("Load from file").clicked() {
-            let res = self.marathon_log.load_from_file(&self.file_name_txt);
-            if let Err(err) = res {
-                error!("error reading file: {}", err.to_string());
+        ui.horizontal(|ui| {
+            if ui.button(tr("Load from file")).clicked() {
+                let res = self.marathon_log.load_from_file(&self.file_name_txt);
+                if let Err(err) = &res {
+                    error!("error reading file: {}", err.to_string());
+                }
+                self.err_state.file_err = res.err();
+            };
+            if ui.button(tr("Review")).clicked() {
+                self.screen = Screen::Review {
+                    return_to: ReviewReturn::Start,
+                };
             }
-        };
-        ui.heading("Headstart time:");
+            if ui.button(tr("Replay file")).clicked() {
+                let mut replay_log = MarathonLog::new(self.settings.duration_secs);
+                match replay_log.load_from_file(&self.file_name_txt) {
+                    Ok(()) => {
+                        self.replay = Some(Replay::new(replay_log));
+                        self.screen = Screen::Replay;
+                        self.err_state.file_err = None;
+                    }
+                    Err(err) => {
+                        error!("error reading file: {}", err.to_string());
+                        self.err_state.file_err = Some(err);
+                    }
+                }
+            };
+        });
+        if let Some(file_err) = &self.err_state.file_err {
+            error_label(ui, &file_err.to_string(), &self.theme);
+        }
+        egui::CollapsingHeader::new(tr("Run details")).show(ui, |ui| {
+            show_metadata_editor(ui, self.marathon_log.metadata_mut());
+        });
+        egui::CollapsingHeader::new(tr("Run library")).show(ui, |ui| {
+            self.show_library(ui);
+        });
+        ui.heading(tr("Headstart time:"));
         ui.add(TextEdit::singleline(&mut self.hours_txt));
         ui.add(TextEdit::singleline(&mut self.mins_txt));
         ui.add(TextEdit::singleline(&mut self.secs_txt));
-        ui.heading("Headstart 5ks:");
+        ui.heading(tr("Headstart count:"));
         ui.add(TextEdit::singleline(&mut self.headstart_5k_txt));
+        ui.heading(tr("Scoring:"));
+        show_scoring_rule_picker(ui, &mut self.marathon_log);
+        ui.heading(tr("Players (comma separated):"));
+        ui.add(TextEdit::singleline(&mut self.players_txt));
+        ui.heading(tr("Team sync address:"));
+        ui.add(TextEdit::singleline(&mut self.sync_addr_txt));
+        ui.horizontal(|ui| {
+            if ui.button(tr("Host sync")).clicked() {
+                match SyncHost::start(self.sync_addr_txt.trim()) {
+                    Ok(host) => {
+                        self.team_sync = TeamSync::Host(host);
+                        self.err_state.sync_err = None;
+                    }
+                    Err(err) => {
+                        error!("error starting sync host: {err}");
+                        self.err_state.sync_err = Some(err);
+                    }
+                }
+            }
+            if ui.button(tr("Join sync")).clicked() {
+                let player_name = self.players_txt.split(',').next().unwrap_or("").trim();
+                let player_name = if player_name.is_empty() {
+                    tr("Player")
+                } else {
+                    player_name.to_owned()
+                };
+                let client = SyncClient::connect(self.sync_addr_txt.trim(), &player_name);
+                self.team_sync = TeamSync::Client(client);
+            }
+        });
+        ui.label(RichText::new(self.team_sync_status()).small());
+        if let Some(sync_err) = &self.err_state.sync_err {
+            let sync_err_txt = trf("could not start sync host: {0}", &[&sync_err]);
+            error_label(ui, &sync_err_txt, &self.theme);
+        }
+        egui::CollapsingHeader::new(tr("Merge / split files")).show(ui, |ui| {
+            self.show_merge_split(ui);
+        });
+        egui::CollapsingHeader::new(tr("Settings")).show(ui, |ui| {
+            self.show_settings(ui);
+        });
     }
 
-    fn show_tracker_display(&mut self, ui: &mut Ui) {
+    /// The marathon length and what happens when it ends, saved to the settings file.
+    fn show_settings(&mut self, ui: &mut Ui) {
         use egui::TextEdit;
 
-        let time_since_epoch = match self
-            .marathon_log
-            .current_epoch
-            .map(|epoch| u32::try_from(epoch.elapsed().as_secs()))
-        {
-            Some(Ok(secs)) => secs,
-            Some(Err(err)) => {
-                self.err_state.time_err = Some(err.to_string());
-                0
+        ui.label(tr("Language:"));
+        let old_language = self.settings.language;
+        egui::ComboBox::from_id_source("language")
+            .selected_text(self.settings.language.to_string())
+            .show_ui(ui, |ui| {
+                for language in Language::ALL {
+                    ui.selectable_value(
+                        &mut self.settings.language,
+                        language,
+                        language.to_string(),
+                    );
+                }
+            });
+        if self.settings.language != old_language {
+            if let Err(err) = set_language(self.settings.language) {
+                warn!("using the built-in translations: {err}");
+                self.settings_status_txt = err.to_string();
+            }
+        }
+        ui.label(tr("Marathon length (HH:MM:SS):"));
+        ui.add(TextEdit::singleline(&mut self.duration_txt));
+        ui.label(tr("Entries after the end:"));
+        egui::ComboBox::from_id_source("late_entry_policy")
+            .selected_text(tr(&self.settings.late_entry_policy.to_string()))
+            .show_ui(ui, |ui| {
+                for policy in LateEntryPolicy::ALL {
+                    ui.selectable_value(
+                        &mut self.settings.late_entry_policy,
+                        policy,
+                        tr(&policy.to_string()),
+                    );
+                }
+            });
+        ui.label(tr("Grace period after the end (seconds):"));
+        ui.add(TextEdit::singleline(&mut self.grace_txt));
+        ui.label(tr("Grace entries:"));
+        egui::ComboBox::from_id_source("grace_rule")
+            .selected_text(tr(&self.settings.grace_rule.to_string()))
+            .show_ui(ui, |ui| {
+                for rule in GraceRule::ALL {
+                    ui.selectable_value(&mut self.settings.grace_rule, rule, tr(&rule.to_string()));
+                }
+            });
+        ui.label(tr("Entries while paused:"));
+        egui::ComboBox::from_id_source("pause_policy")
+            .selected_text(tr(&self.settings.pause_policy.to_string()))
+            .show_ui(ui, |ui| {
+                for policy in PausePolicy::ALL {
+                    ui.selectable_value(
+                        &mut self.settings.pause_policy,
+                        policy,
+                        tr(&policy.to_string()),
+                    );
+                }
+            });
+        ui.checkbox(
+            &mut self.settings.auto_save_at_end,
+            tr("Save when the marathon ends"),
+        );
+        egui::CollapsingHeader::new(tr("Alerts")).show(ui, |ui| {
+            self.show_alert_settings(ui);
+        });
+        egui::CollapsingHeader::new(tr("Droughts")).show(ui, |ui| {
+            self.show_drought_settings(ui);
+        });
+        egui::CollapsingHeader::new(tr("Theme")).show(ui, |ui| {
+            self.show_theme_settings(ui);
+        });
+        if ui.button(tr("Save settings")).clicked() && self.apply_settings() {
+            self.settings_status_txt = match self.settings.save(SETTINGS_FILE) {
+                Ok(()) => trf("saved to {0}", &[&SETTINGS_FILE]),
+                Err(err) => {
+                    error!("error saving settings: {err}");
+                    err.to_string()
+                }
+            };
+        }
+        ui.label(RichText::new(&self.settings_status_txt).small());
+    }
+
+    fn show_alert_settings(&mut self, ui: &mut Ui) {
+        let alerts = &mut self.settings.alerts;
+        let hit_name = self.marathon_log.scoring_rule().hit_name();
+        ui.horizontal(|ui| {
+            ui.label(trf("Every how many {0}s (0 for off):", &[&hit_name]));
+            ui.add(egui::DragValue::new(&mut alerts.every_nth_hit).range(0..=1000));
+        });
+        ui.checkbox(&mut alerts.hourly, tr("Hourly check-in"));
+        ui.checkbox(
+            &mut alerts.behind_target,
+            tr("Falling behind the target pace"),
+        );
+        ui.horizontal(|ui| {
+            ui.label(trf(
+                "No {0} for this many minutes (0 for off):",
+                &[&hit_name],
+            ));
+            ui.add(egui::DragValue::new(&mut alerts.drought_mins).range(0..=24 * 60));
+        });
+        ui.checkbox(&mut alerts.final_hour, tr("Final hour countdown"));
+        ui.checkbox(&mut alerts.sound, tr("Play a sound"));
+    }
+
+    /// The theme picker, with the presets and the files in the themes folder. A new pick is
+    /// applied straight away.
+    fn show_theme_settings(&mut self, ui: &mut Ui) {
+        let mut source = self.settings.theme.clone();
+        egui::ComboBox::from_id_source("theme")
+            .selected_text(tr(&source.to_string()))
+            .show_ui(ui, |ui| {
+                for preset in ThemePreset::ALL {
+                    let preset_source = ThemeSource::Preset(preset);
+                    ui.selectable_value(&mut source, preset_source, tr(&preset.to_string()));
+                }
+                for path in &self.theme_files {
+                    let file_source = ThemeSource::File(path.clone());
+                    ui.selectable_value(&mut source, file_source, path);
+                }
+            });
+        if source != self.settings.theme {
+            match Theme::from_source(&source) {
+                Ok(theme) => {
+                    if let Err(err) = theme_style::apply_theme(&theme, ui.ctx()) {
+                        warn!("using the built-in fonts: {err}");
+                        self.settings_status_txt = err.to_string();
+                    }
+                    self.theme = theme;
+                    self.settings.theme = source;
+                }
+                Err(err) => {
+                    error!("error loading theme: {err}");
+                    self.settings_status_txt = err.to_string();
+                }
+            }
+        }
+        if ui.button(tr("Save as theme file")).clicked() {
+            self.settings_status_txt = match self.theme.save_to_themes_dir() {
+                Ok(path) => {
+                    self.theme_files = theme_files();
+                    trf("saved to {0}, edit it and pick it above", &[&path])
+                }
+                Err(err) => {
+                    error!("error saving theme: {err}");
+                    err.to_string()
+                }
+            };
+        }
+    }
+
+    fn show_drought_settings(&mut self, ui: &mut Ui) {
+        let thresholds = &mut self.settings.drought_thresholds;
+        let hit_name = self.marathon_log.scoring_rule().hit_name();
+        ui.label(trf(
+            "Minutes without a {0} until the timer turns",
+            &[&hit_name],
+        ));
+        ui.horizontal(|ui| {
+            ui.label(tr("yellow:"));
+            ui.add(egui::DragValue::new(&mut thresholds.warning_mins).range(1..=24 * 60));
+            ui.label(tr("red:"));
+            ui.add(egui::DragValue::new(&mut thresholds.alarm_mins).range(1..=24 * 60));
+        });
+        ui.label(RichText::new(tr("Droughts that turn red are recorded in the log.")).small());
+    }
+
+    /// Reads the marathon length and grace period from their fields and passes the settings on
+    /// to the log. Returns false if either isn't valid, in which case the old value is kept.
+    fn apply_settings(&mut self) -> bool {
+        let valid_grace = match u32::from_str(self.grace_txt.trim()) {
+            Ok(grace_secs) => {
+                self.settings.grace_secs = grace_secs;
+                true
+            }
+            Err(err) => {
+                self.settings_status_txt = trf("invalid grace period: {0}", &[&err]);
+                false
             }
-            None => 0,
         };
-        let (is_paused, pause_btn_txt) = match self.marathon_log.current_epoch {
-            Some(_) => (false, "Pause"),
-            None => (true, "Unpause"),
+        let valid = match HoursMinutesSeconds::from_str(&self.duration_txt) {
+            Ok(hms) if hms.total_secs() > 0 => {
+                self.settings.duration_secs = hms.total_secs();
+                true
+            }
+            Ok(_) => {
+                self.settings_status_txt = tr("the marathon length can't be 0");
+                false
+            }
+            Err(err) => {
+                self.settings_status_txt = trf("invalid marathon length: {0}", &[&err]);
+                false
+            }
         };
-        let countdown = calculate_countdown(time_since_epoch, self.marathon_log.epoch_offset_secs);
-        let time_since_5k = self
-            .marathon_log
-            .time_since_last_5k()
-            .map_or("".to_owned(), time_counter);
-        let estimated_pace = self
-            .marathon_log
-            .estimate_pace()
-            .map_or("".to_owned(), |x| x.to_string());
+        self.marathon_log
+            .set_duration_secs(self.settings.duration_secs);
+        self.marathon_log
+            .set_late_entry_policy(self.settings.late_entry_policy);
+        self.marathon_log
+            .set_pause_policy(self.settings.pause_policy);
+        self.marathon_log
+            .set_grace_period(self.settings.grace_secs, self.settings.grace_rule);
+        self.marathon_log
+            .set_long_drought_secs(self.settings.drought_thresholds.alarm_mins * 60);
+        valid && valid_grace
+    }
 
-        ui.horizontal_top(|ui| {
-            ui.vertical(|ui| {
-                ui.heading("Time left:");
-                ui.label(countdown);
-                ui.label("");
-                ui.heading("Last 5k:");
-                ui.label(time_since_5k);
+    /// The saved runs in the current folder, filtered by the search box. Picking a run puts its
+    /// file in the file name field.
+    fn show_library(&mut self, ui: &mut Ui) {
+        use egui::TextEdit;
 
-                let _test = ui.label("");
-                ui.label("");
-                if ui.add(Button::new(pause_btn_txt)).clicked() {
-                    if is_paused {
-                        self.marathon_log.current_epoch = Some(Instant::now());
-                        self.err_state.timer_paused = false;
-                    } else {
-                        // is_paused is false, so the current_epoch must be Some(_)
-                        let elapsed = self.marathon_log.current_epoch.unwrap().elapsed().as_secs();
-                        self.marathon_log.epoch_offset_secs += u32::try_from(elapsed)
-                            .expect("this timer should not run for 136 years");
-                        self.marathon_log.current_epoch = None;
-                    }
+        ui.horizontal(|ui| {
+            ui.label(tr("Search:"));
+            ui.add(TextEdit::singleline(&mut self.library_query_txt).desired_width(160.0));
+            if ui.button(tr("Refresh")).clicked() {
+                match scan_runs(".", self.settings.duration_secs) {
+                    Ok(runs) => self.library_runs = runs,
+                    Err(err) => error!("error reading run library: {err}"),
+                }
+            }
+        });
+        egui::Grid::new("run_library").show(ui, |ui| {
+            for run in search(&self.library_runs, &self.library_query_txt) {
+                let metadata = &run.metadata;
+                let path = run.path.trim_start_matches("./");
+                if ui
+                    .selectable_label(self.file_name_txt == path, path)
+                    .clicked()
+                {
+                    self.file_name_txt = path.to_owned();
+                }
+                let details = format!(
+                    "{} {} {}",
+                    metadata.player, metadata.map_name, metadata.game_mode
+                );
+                ui.label(RichText::new(details.trim()).small());
+                let count = format!("{}: {}", run.scoring_rule.count_label(), fmt_int(run.count));
+                ui.label(RichText::new(count).small());
+                ui.label(RichText::new(metadata.started_at_txt()).small());
+                ui.end_row();
+            }
+        });
+    }
+
+    /// Merging the files listed in the merge field into a new save file, and splitting the file
+    /// in the file name field at a marathon time.
+    fn show_merge_split(&mut self, ui: &mut Ui) {
+        use egui::TextEdit;
+
+        ui.label(tr("Files to merge (comma separated):"));
+        ui.add(TextEdit::singleline(&mut self.merge_files_txt));
+        ui.checkbox(&mut self.merge_rebase, tr("Move overlapping files later"));
+        if ui.button(tr("Merge")).clicked() {
+            let paths: Vec<&str> = self
+                .merge_files_txt
+                .split(',')
+                .map(str::trim)
+                .filter(|path| !path.is_empty())
+                .collect();
+            let options = MergeOptions {
+                rebase: self.merge_rebase,
+            };
+            let res =
+                merge_files(&paths, options, self.settings.duration_secs).and_then(|report| {
+                    let path = report.merged.save_to_file()?;
+                    Ok(format!(
+                        "{}\n{}",
+                        report.summary(),
+                        trf("saved to {0}", &[&path])
+                    ))
+                });
+            self.show_merge_result(res);
+        }
+        ui.label(tr("Split file from file name field at (HH:MM:SS):"));
+        ui.add(TextEdit::singleline(&mut self.split_at_txt));
+        if ui.button(tr("Split")).clicked() {
+            match HoursMinutesSeconds::from_str(&self.split_at_txt) {
+                Ok(hms) => {
+                    let res =
+                        MarathonLog::from_file(self.settings.duration_secs, &self.file_name_txt)
+                            .and_then(|marathon_log| {
+                                let (before, after) = split_log(&marathon_log, hms.total_secs());
+                                let before_path = before.save_to_file()?;
+                                let after_path = after.save_to_file()?;
+                                Ok(trf("saved to {0} and {1}", &[&before_path, &after_path]))
+                            });
+                    self.show_merge_result(res);
                 }
+                Err(err) => self.merge_report_txt = trf("invalid split time: {0}", &[&err]),
+            }
+        }
+        ui.label(RichText::new(&self.merge_report_txt).small());
+    }
+
+    fn show_merge_result(&mut self, res: Result<String, PersistError>) {
+        match res {
+            Ok(report_txt) => {
+                self.merge_report_txt = report_txt;
+                self.err_state.file_err = None;
+            }
+            Err(err) => {
+                error!("error merging files: {err}");
+                self.merge_report_txt.clear();
+                self.err_state.file_err = Some(err);
+            }
+        }
+    }
+
+    fn show_tracker_display(&mut self, ui: &mut Ui) {
+        use egui::TextEdit;
+
+        if let TeamSync::Host(host) = &mut self.team_sync {
+            host.poll(&mut self.marathon_log);
+        }
+        if !self.marathon_ended
+            && self.marathon_log.is_over()
+            && !self.marathon_log.in_grace_period()
+        {
+            self.end_marathon();
+        }
+
+        let snapshot = match self.marathon_log.snapshot() {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                self.err_state.time_err = Some(err);
+                self.marathon_log.snapshot_at(0)
+            }
+        };
+        // an ended marathon isn't paused, and can't be paused or resumed any more
+        let (is_paused, pause_btn_txt) = match self.marathon_log.is_paused() {
+            false => (false, tr("Pause")),
+            true => (true, tr("Unpause")),
+        };
+        let can_pause = !self.marathon_ended;
+        if is_paused && can_pause {
+            let queued = self.marathon_log.queued_entries().len();
+            show_paused_banner(ui, self.marathon_log.pause_policy(), queued, &self.theme);
+        }
+        let panels = self.theme.panels;
+        self.check_alerts(&snapshot);
+        if panels.alerts {
+            show_flashes(ui, &self.flashes, &snapshot, &self.theme);
+        }
+
+        let thresholds = self.settings.drought_thresholds;
+        let target = self.marathon_log.metadata().target;
+        let (pause_btn, add_5k_btn) = show_snapshot(
+            ui,
+            &snapshot,
+            target,
+            &thresholds,
+            &self.theme,
+            (&pause_btn_txt, can_pause),
+            &tr("Add 5k"),
+        );
+        if pause_btn.clicked() {
+            if is_paused {
+                let added = self.marathon_log.resume();
+                self.sync_last_entries(added);
+                self.err_state.timer_paused = false;
+                self.err_state.entry_paused = false;
+            } else if let Err(err) = self.marathon_log.pause() {
+                self.err_state.time_err = Some(err);
+            }
+        }
+        if add_5k_btn.clicked() {
+            let res = self.marathon_log.try_add_entry(5000);
+            self.handle_quick_entry(res);
+        }
+        if let Some(secs_left) = self.marathon_log.grace_secs_left() {
+            let grace_txt = trf(
+                "Time's up: finish the round, {0} left",
+                &[&time_counter(secs_left)],
+            );
+            ui.label(RichText::new(grace_txt).strong());
+        }
+        if panels.players && self.marathon_log.player_names().len() > 1 {
+            self.show_players(ui);
+        }
+        if panels.games {
+            self.show_games(ui);
+        }
+        if panels.splits {
+            egui::CollapsingHeader::new(tr("Splits")).show(ui, |ui| {
+                self.show_splits(ui, snapshot.elapsed_secs);
             });
-            ui.vertical(|ui| {
-                ui.heading("5k count:");
-                ui.label(self.marathon_log.total_5ks.to_string());
-                ui.label("");
-                ui.heading("Pace:");
-                ui.label(estimated_pace);
+        }
 
-                ui.label("");
-                ui.label("");
-                if ui.add(Button::new("Add 5k")).clicked() {
-                    match self.marathon_log.try_add_entry(5000) {
-                        AddEntryResult::Ok => {
-                            self.err_state.invalid_score = None;
-                        }
-                        AddEntryResult::TimerPaused => {
-                            self.err_state.timer_paused = true;
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 8.0;
+            if panels.entry_buttons {
+                if ui.add(Button::new(tr("Add miss"))).clicked() {
+                    // a miss uses the score in the paste box, or 0 if it's empty
+                    let res = match self.score_input_txt.trim() {
+                        "" => Ok(0),
+                        txt => score_from_str(txt),
+                    };
+                    match res {
+                        Ok(score) => {
+                            let res = self.marathon_log.try_add_miss(score);
+                            self.score_input_txt.clear();
+                            self.handle_quick_entry(res);
                         }
-                        AddEntryResult::ImpossibleScore { score: _ } => {
-                            unreachable!();
+                        Err(err) => {
+                            self.score_input_txt.clear();
+                            self.err_state.invalid_score = Some(err.into());
                         }
-                    };
+                    }
                 }
-            });
+                if ui.add(Button::new(tr("No guess"))).clicked() {
+                    let res = self.marathon_log.add_no_guess();
+                    self.handle_quick_entry(res);
+                }
+                if ui.add(Button::new(tr("Skip game"))).clicked() {
+                    let res = self.marathon_log.add_skipped();
+                    self.handle_quick_entry(res);
+                }
+                if ui.add(Button::new(tr("New game"))).clicked() {
+                    self.marathon_log.start_new_game();
+                }
+            }
+            if ui.add(Button::new(tr("Review"))).clicked() {
+                self.screen = Screen::Review {
+                    return_to: ReviewReturn::Tracker,
+                };
+            }
+            if self.marathon_ended && ui.add(Button::new(tr("Summary"))).clicked() {
+                self.screen = Screen::Summary;
+            }
         });
-
         ui.separator();
-        /* if ui.button("print entries").clicked() {
+        /* if ui.button(tr("print entries")).clicked() {
             self.marathon_log.print_entries();
         } */
-        ui.heading("Paste score:");
+        ui.heading(tr("Paste score:"));
         let response = ui.add(TextEdit::multiline(&mut self.score_input_txt).desired_rows(2));
         if response.changed() && (self.score_input_txt.chars().filter(|&c| c == '\n').count() >= 1)
         {
-            let score_conv_res = score_from_str(&self.score_input_txt);
-            if let Err(err) = score_conv_res {
+            let score_conv_res = parse_round_result(&self.score_input_txt);
+            if is_new_game_command(&self.score_input_txt) {
+                self.marathon_log.start_new_game();
                 self.score_input_txt.clear();
-                self.err_state.invalid_score = Some(err.to_string());
+            } else if let Err(err) = score_conv_res {
+                self.score_input_txt.clear();
+                self.err_state.invalid_score = Some(err.into());
             } else {
                 // score_conv_res is known to not be an error
-                match self.marathon_log.try_add_entry(score_conv_res.unwrap()) {
-                    AddEntryResult::Ok => {
+                let round_result = score_conv_res.unwrap();
+                log_round_details(&round_result);
+                match self.marathon_log.try_add_entry(round_result.score) {
+                    AddEntryResult::Ok | AddEntryResult::Grace => {
                         self.score_input_txt.clear();
                         self.err_state.invalid_score = None;
+                        self.sync_last_entry();
                     }
                     AddEntryResult::TimerPaused => {
                         self.clear_extra_lines();
                         self.err_state.timer_paused = true;
+                        self.sync_last_entry();
+                    }
+                    AddEntryResult::Queued => {
+                        self.score_input_txt.clear();
+                        self.err_state.invalid_score = None;
+                    }
+                    AddEntryResult::Paused => {
+                        // the score stays in the box, to be pasted again after unpausing
+                        self.clear_extra_lines();
+                        self.err_state.entry_paused = true;
+                    }
+                    AddEntryResult::Late => {
+                        self.score_input_txt.clear();
+                        self.err_state.late_entry = true;
+                        self.sync_last_entry();
+                    }
+                    AddEntryResult::MarathonOver => {
+                        self.score_input_txt.clear();
+                        self.err_state.entry_rejected = true;
                     }
                     AddEntryResult::ImpossibleScore { score } => {
                         self.score_input_txt.clear();
-                        self.err_state.invalid_score = Some(score.to_string());
+                        self.err_state.invalid_score =
+                            Some(TrackerError::ImpossibleScore { score });
                     }
                 };
             }
         }
-        ui.label(
-            RichText::new(self.err_state.get_err_txt())
-                .color(egui::Color32::from_rgb(240, 10, 10))
-                .small(),
+        error_label(ui, &self.err_state.get_err_txt(), &self.theme);
+        if !matches!(self.team_sync, TeamSync::Off) {
+            ui.label(RichText::new(self.team_sync_status()).small());
+        }
+        egui::CollapsingHeader::new(tr("Run details")).show(ui, |ui| {
+            show_metadata_editor(ui, self.marathon_log.metadata_mut());
+        });
+    }
+
+    /// Shows the game being played, and the game stats so far.
+    fn show_games(&mut self, ui: &mut Ui) {
+        ui.separator();
+        let stats = self.marathon_log.game_stats();
+        let games_played = stats.games;
+        let game_txt = match self.marathon_log.current_game() {
+            Some(game) => trf(
+                "Game {0}: round {1}/{2}, {3} points",
+                &[
+                    &games_played,
+                    &game.rounds.len(),
+                    &ROUNDS_PER_GAME,
+                    &fmt_int(game.total_score()),
+                ],
+            ),
+            None => trf("Game {0}: not started", &[&(games_played + 1)]),
+        };
+        ui.label(RichText::new(game_txt).small());
+        egui::CollapsingHeader::new(tr("Games")).show(ui, |ui| {
+            let best_total = stats.best_total.map_or("".to_owned(), fmt_int);
+            ui.label(RichText::new(trf("25ks: {0}", &[&stats.twenty_five_ks])).small());
+            ui.label(RichText::new(trf("best game: {0}", &[&best_total])).small());
+            ui.label(RichText::new(trf("skipped games: {0}", &[&stats.skipped_games])).small());
+            ui.spacing_mut().item_spacing.x = 12.0;
+            egui::Grid::new("round_positions").show(ui, |ui| {
+                for (i, position) in stats.positions.iter().enumerate() {
+                    let hit_rate = position.hit_rate().map_or("".to_owned(), fmt_percent);
+                    let mean_score = position.mean_score().map_or("".to_owned(), |mean| {
+                        trf("{0} avg", &[&fmt_decimal(mean, 0)])
+                    });
+                    let spoken = trf(
+                        "Round {0}: {1} hit rate, {2}",
+                        &[&(i + 1), &hit_rate, &mean_score],
+                    );
+                    metric_label(
+                        ui,
+                        RichText::new(trf("Round {0}", &[&(i + 1)])).small(),
+                        &spoken,
+                    );
+                    ui.label(RichText::new(hit_rate).small());
+                    ui.label(RichText::new(mean_score).small());
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    /// Shows a button to make each player active, and the numbers for each player.
+    fn show_players(&mut self, ui: &mut Ui) {
+        ui.separator();
+        let player_stats = self.marathon_log.player_stats();
+        let rule = self.marathon_log.scoring_rule();
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 8.0;
+            for (i, stats) in player_stats.iter().enumerate() {
+                let player = i as u8;
+                let is_active = self.marathon_log.active_player() == player;
+                if ui.selectable_label(is_active, &stats.name).clicked() {
+                    self.marathon_log.set_active_player(player);
+                }
+            }
+        });
+        egui::CollapsingHeader::new(tr("Players")).show(ui, |ui| {
+            ui.spacing_mut().item_spacing.x = 12.0;
+            egui::Grid::new("player_stats").show(ui, |ui| {
+                for stats in &player_stats {
+                    let per_hour = stats.per_hour.map_or("".to_owned(), |per_hour| {
+                        format!("{}/h", fmt_decimal(per_hour, 1))
+                    });
+                    let spoken = trf(
+                        "{0}: {1} {2}, {3}, active for {4}",
+                        &[
+                            &stats.name,
+                            &fmt_int(stats.count),
+                            &rule.count_label(),
+                            &per_hour,
+                            &spoken_duration(stats.active_secs),
+                        ],
+                    );
+                    metric_label(ui, RichText::new(&stats.name).small(), &spoken);
+                    ui.label(RichText::new(fmt_int(stats.count)).small());
+                    ui.label(RichText::new(per_hour).small());
+                    ui.label(RichText::new(time_counter(stats.active_secs)).small());
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    fn show_replay_display(&mut self, ui: &mut Ui) {
+        let Some(replay) = &mut self.replay else {
+            self.screen = Screen::Start;
+            return;
+        };
+        replay.advance(Duration::from_secs_f32(ui.input(|i| i.stable_dt)));
+
+        let play_btn_txt = if replay.is_playing() {
+            tr("Pause")
+        } else {
+            tr("Play")
+        };
+        let thresholds = self.settings.drought_thresholds;
+        let (play_btn, back_btn) = show_snapshot(
+            ui,
+            &replay.snapshot(),
+            replay.metadata().target,
+            &thresholds,
+            &self.theme,
+            (&play_btn_txt, true),
+            &tr("Back"),
         );
+        if play_btn.clicked() {
+            replay.toggle_playing();
+        }
+        if back_btn.clicked() {
+            self.replay = None;
+            self.screen = Screen::Start;
+            return;
+        }
+
+        ui.separator();
+        ui.heading(tr("Replay position:"));
+        let mut position = replay.position_secs();
+        let position_slider = Slider::new(&mut position, 0..=replay.duration_secs())
+            .custom_formatter(|secs, _| time_counter(secs as u32));
+        if ui.add(position_slider).changed() {
+            replay.seek(position);
+        }
+        ui.heading(tr("Replay speed:"));
+        let mut speed = replay.speed();
+        let speed_slider = Slider::new(&mut speed, MIN_SPEED..=MAX_SPEED)
+            .logarithmic(true)
+            .suffix("x");
+        if ui.add(speed_slider).changed() {
+            replay.set_speed(speed);
+        }
+    }
+
+    /// Round timing and details of the run in the tracker, which is the loaded file before the
+    /// timer is started.
+    fn show_review_display(&mut self, ui: &mut Ui, return_to: ReviewReturn) {
+        ui.horizontal(|ui| {
+            if ui.button(tr("Back")).clicked() {
+                self.screen = match return_to {
+                    ReviewReturn::Start => Screen::Start,
+                    ReviewReturn::Tracker => Screen::Tracker,
+                    ReviewReturn::Summary => Screen::Summary,
+                };
+            }
+            self.show_export_buttons(ui);
+        });
+        if let Some(file_err) = &self.err_state.file_err {
+            error_label(ui, &file_err.to_string(), &self.theme);
+        }
+
+        egui::CollapsingHeader::new(tr("Run details")).show(ui, |ui| {
+            show_metadata_editor(ui, self.marathon_log.metadata_mut());
+        });
+
+        let rule = self.marathon_log.scoring_rule();
+        let analysis = analyze_timing(
+            self.marathon_log.entries(),
+            &rule,
+            DEFAULT_BUCKET_SECS,
+            REVIEW_SLOWEST_COUNT,
+        );
+        ui.separator();
+        ui.heading(tr("Round times:"));
+        let mean = analysis
+            .mean_secs
+            .map_or("".to_owned(), |secs| format!("{}s", fmt_decimal(secs, 1)));
+        let median = analysis
+            .median_secs
+            .map_or("".to_owned(), |secs| format!("{}s", fmt_int(secs)));
+        ui.label(trf(
+            "{0} rounds, mean {1}, median {2}",
+            &[&fmt_int(analysis.rounds as u64), &mean, &median],
+        ));
+        egui::Grid::new("time_spent").show(ui, |ui| {
+            for (name, time_spent) in [
+                (trf("{0} rounds", &[&rule.hit_name()]), &analysis.hits),
+                (tr("Misses"), &analysis.misses),
+                (tr("Skips"), &analysis.skipped),
+            ] {
+                let mean = time_spent.mean_secs().map_or("".to_owned(), |secs| {
+                    trf("{0}s avg", &[&fmt_decimal(secs, 1)])
+                });
+                let total = u32::try_from(time_spent.secs).unwrap_or(u32::MAX);
+                let spoken = trf(
+                    "{0}: {1} rounds, {2} in total, {3}",
+                    &[&name, &time_spent.rounds, &spoken_duration(total), &mean],
+                );
+                metric_label(ui, RichText::new(&name).small(), &spoken);
+                ui.label(RichText::new(time_spent.rounds.to_string()).small());
+                ui.label(RichText::new(time_counter(total)).small());
+                ui.label(RichText::new(mean).small());
+                ui.end_row();
+            }
+        });
+
+        let max_count = analysis
+            .histogram
+            .iter()
+            .map(|b| b.count)
+            .max()
+            .unwrap_or(0);
+        egui::Grid::new("round_time_histogram").show(ui, |ui| {
+            for bucket in &analysis.histogram {
+                let range = match bucket.end_secs {
+                    Some(end) => format!("{}-{end}s", bucket.start_secs),
+                    None => format!("{}s+", bucket.start_secs),
+                };
+                let fraction = match max_count {
+                    0 => 0.0,
+                    _ => bucket.count as f32 / max_count as f32,
+                };
+                ui.label(RichText::new(range).small());
+                ui.add(
+                    egui::ProgressBar::new(fraction)
+                        .desired_width(180.0)
+                        .text(bucket.count.to_string()),
+                );
+                ui.end_row();
+            }
+        });
+
+        let last_entry_secs = self
+            .marathon_log
+            .last_entry()
+            .map_or(0, |entry| entry.time_secs.saturating_add(1));
+        let end_secs = self
+            .marathon_log
+            .elapsed_secs()
+            .unwrap_or(0)
+            .max(last_entry_secs);
+        egui::CollapsingHeader::new(tr("Splits")).show(ui, |ui| {
+            self.show_splits(ui, end_secs.min(self.marathon_log.duration_secs()));
+        });
+
+        ui.heading(tr("Slowest rounds:"));
+        egui::Grid::new("slowest_rounds").show(ui, |ui| {
+            for round in &analysis.slowest {
+                ui.label(RichText::new(time_counter(round.entry.time_secs)).small());
+                ui.label(RichText::new(format!("{}s", round.secs)).small());
+                ui.label(RichText::new(tr(&round.entry.kind.to_string())).small());
+                ui.label(RichText::new(fmt_int(round.entry.score)).small());
+                ui.end_row();
+            }
+        });
+
+        let long_droughts = self.marathon_log.long_droughts();
+        if !long_droughts.is_empty() {
+            ui.heading(tr("Long droughts:"));
+            egui::Grid::new("long_droughts").show(ui, |ui| {
+                for drought in long_droughts {
+                    let span = format!(
+                        "{} - {}",
+                        time_counter(drought.start_secs),
+                        time_counter(drought.end_secs)
+                    );
+                    ui.label(RichText::new(span).small());
+                    ui.label(RichText::new(time_counter(drought.secs())).small());
+                    let rounds = trf("{0} rounds", &[&drought.rounds]);
+                    ui.label(RichText::new(rounds).small());
+                    ui.end_row();
+                }
+            });
+        }
+    }
+
+    /// Buttons for exporting and saving the run, shared by the review and summary screens.
+    fn show_export_buttons(&mut self, ui: &mut Ui) {
+        if ui.button(tr("Export CSV")).clicked() {
+            self.export_csv();
+        }
+        if ui.button(tr("HTML report")).clicked() {
+            self.export_report();
+        }
+        if ui.button(tr("Charts")).clicked() {
+            self.export_charts();
+        }
+        if ui.button(tr("Save copy")).clicked() {
+            match self.marathon_log.save_to_file() {
+                Ok(path) => {
+                    info!("saved review copy to {path}");
+                    self.err_state.file_err = None;
+                }
+                Err(err) => {
+                    error!("error saving to file: {err}");
+                    self.err_state.file_err = Some(err);
+                }
+            }
+        }
+    }
+
+    /// Stops the clock, saves the log if the settings say so, and shows the summary.
+    fn end_marathon(&mut self) {
+        self.marathon_ended = true;
+        self.marathon_log.end();
+        info!("the marathon is over");
+        if self.settings.auto_save_at_end {
+            self.auto_save_txt = match self.marathon_log.save_to_file() {
+                Ok(path) => trf("saved to {0}", &[&path]),
+                Err(err) => {
+                    error!("error saving to file: {err}");
+                    trf("could not save: {0}", &[&err])
+                }
+            };
+        }
+        self.screen = Screen::Summary;
+    }
+
+    /// The final numbers of the run, with the same exports as the review screen.
+    fn show_summary_display(&mut self, ui: &mut Ui) {
+        ui.heading(tr("Marathon over!"));
+        ui.horizontal(|ui| {
+            if ui.button(tr("Tracker")).clicked() {
+                self.screen = Screen::Tracker;
+            }
+            if ui.button(tr("Review")).clicked() {
+                self.screen = Screen::Review {
+                    return_to: ReviewReturn::Summary,
+                };
+            }
+        });
+        ui.horizontal_wrapped(|ui| {
+            self.show_export_buttons(ui);
+        });
+        if !self.auto_save_txt.is_empty() {
+            ui.label(RichText::new(&self.auto_save_txt).small());
+        }
+        if let Some(file_err) = &self.err_state.file_err {
+            error_label(ui, &file_err.to_string(), &self.theme);
+        }
+        ui.separator();
+
+        let rule = self.marathon_log.scoring_rule();
+        let numbers = headline_stats(&self.marathon_log).rows(&rule);
+        egui::Grid::new("summary").show(ui, |ui| {
+            for (name, value) in numbers {
+                ui.label(RichText::new(&name).small());
+                metric_label(ui, value.as_str(), &format!("{name}: {value}"));
+                ui.end_row();
+            }
+        });
+    }
+
+    /// The marathon up to `end_secs` split into intervals, compared with the target and the run
+    /// in the compare file.
+    fn show_splits(&mut self, ui: &mut Ui, end_secs: u32) {
+        use egui::TextEdit;
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Every"));
+            ui.add(egui::DragValue::new(&mut self.split_mins).range(1..=24 * 60));
+            ui.label(tr("minutes"));
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("Compare with:"));
+            ui.add(TextEdit::singleline(&mut self.compare_file_txt).desired_width(120.0));
+            if ui.button(tr("Load")).clicked() {
+                let path = self.compare_file_txt.trim();
+                match MarathonLog::from_file(self.settings.duration_secs, path) {
+                    Ok(compare_log) => {
+                        self.compare_log = Some(compare_log);
+                        self.err_state.file_err = None;
+                    }
+                    Err(err) => {
+                        error!("error reading file: {err}");
+                        self.compare_log = None;
+                        self.err_state.file_err = Some(err);
+                    }
+                }
+            }
+        });
+
+        let comparisons = log_splits(
+            &self.marathon_log,
+            self.split_mins * 60,
+            end_secs,
+            self.compare_log.as_ref(),
+        );
+        ui.spacing_mut().item_spacing.x = 10.0;
+        egui::Grid::new("splits").show(ui, |ui| {
+            for heading in [
+                "From", "Count", "Rounds", "Hits", "Avg", "Total", "Target", "Prev",
+            ] {
+                ui.label(RichText::new(tr(heading)).small());
+            }
+            ui.end_row();
+            for comparison in &comparisons {
+                let split = &comparison.split;
+                let hit_rate = split.hit_rate().map_or("".to_owned(), fmt_percent);
+                let mean_score = split
+                    .mean_score()
+                    .map_or("".to_owned(), |mean| fmt_decimal(mean, 0));
+                let vs_target = comparison
+                    .vs_target()
+                    .map_or("".to_owned(), |diff| format!("{diff:+.0}"));
+                let vs_previous = comparison
+                    .vs_previous()
+                    .map_or("".to_owned(), |diff| format!("{diff:+}"));
+                for txt in [
+                    time_counter(split.start_secs),
+                    fmt_int(split.count),
+                    fmt_int(split.rounds),
+                    hit_rate,
+                    mean_score,
+                    fmt_int(split.cumulative_count),
+                    vs_target,
+                    vs_previous,
+                ] {
+                    ui.label(RichText::new(txt).small());
+                }
+                ui.end_row();
+            }
+        });
+    }
+
+    /// The file name the exports are named after, without the extension.
+    fn export_name(&self) -> String {
+        let name = self.file_name_txt.trim().trim_end_matches(".ron");
+        if name.is_empty() { "run" } else { name }.to_owned()
+    }
+
+    /// Writes the entries and splits of the run to CSV files named after the file name field.
+    fn export_csv(&mut self) {
+        let name = self.export_name();
+        let entries_path = format!("{name}_entries.csv");
+        let splits_path = format!("{name}_splits.csv");
+        let comparisons = log_splits(
+            &self.marathon_log,
+            self.split_mins * 60,
+            self.marathon_log.duration_secs(),
+            self.compare_log.as_ref(),
+        );
+        let res = write_csv(&entries_path, &entries_csv(&self.marathon_log))
+            .and_then(|()| write_csv(&splits_path, &splits_csv(&comparisons)));
+        match res {
+            Ok(()) => {
+                info!("exported to {entries_path} and {splits_path}");
+                self.err_state.file_err = None;
+            }
+            Err(err) => {
+                error!("error exporting: {err}");
+                self.err_state.file_err = Some(err);
+            }
+        }
+    }
+
+    fn export_report(&mut self) {
+        let path = format!("{}_report.html", self.export_name());
+        match write_report(&path, &html_report(&self.marathon_log)) {
+            Ok(()) => {
+                info!("report saved to {path}");
+                self.err_state.file_err = None;
+            }
+            Err(err) => {
+                error!("error saving report: {err}");
+                self.err_state.file_err = Some(err);
+            }
+        }
+    }
+
+    /// Saves the charts as SVG and PNG, compared with the run picked for the splits if there is
+    /// one.
+    fn export_charts(&mut self) {
+        let prefix = self.export_name();
+        match save_charts(&self.marathon_log, self.compare_log.as_ref(), &prefix) {
+            Ok(paths) => {
+                info!("saved charts to {}", paths.join(", "));
+                self.err_state.file_err = None;
+            }
+            Err(err) => {
+                error!("error saving charts: {err}");
+                self.err_state.file_err = Some(err);
+            }
+        }
+    }
+
+    /// Flashes the alerts that fire at `snapshot`, and plays their tones if sound is on.
+    fn check_alerts(&mut self, snapshot: &Snapshot) {
+        let target = self.marathon_log.metadata().target;
+        let alert_settings = self.settings.alerts;
+        for alert in self.alert_tracker.check(&alert_settings, snapshot, target) {
+            info!("alert: {alert:?}");
+            if alert_settings.sound {
+                if let Err(err) = play_alert_sound(&alert) {
+                    warn!("could not play alert sound: {err}");
+                }
+            }
+            self.flashes.push((alert, Instant::now()));
+        }
+        self.flashes
+            .retain(|(_, fired_at)| fired_at.elapsed() < FLASH_DURATION);
+    }
+
+    /// Updates the error state after an entry was added from one of the buttons.
+    fn handle_quick_entry(&mut self, res: AddEntryResult) {
+        match res {
+            AddEntryResult::Ok | AddEntryResult::Grace => {
+                self.err_state.invalid_score = None;
+                self.sync_last_entry();
+            }
+            AddEntryResult::TimerPaused => {
+                self.err_state.timer_paused = true;
+                self.sync_last_entry();
+            }
+            AddEntryResult::Queued => {
+                self.err_state.invalid_score = None;
+            }
+            AddEntryResult::Paused => {
+                self.err_state.entry_paused = true;
+            }
+            AddEntryResult::Late => {
+                self.err_state.late_entry = true;
+                self.sync_last_entry();
+            }
+            AddEntryResult::MarathonOver => {
+                self.err_state.entry_rejected = true;
+            }
+            AddEntryResult::ImpossibleScore { score } => {
+                self.err_state.invalid_score = Some(TrackerError::ImpossibleScore { score });
+            }
+        };
+    }
+
+    fn team_sync_status(&self) -> String {
+        match &self.team_sync {
+            TeamSync::Off => "".to_owned(),
+            TeamSync::Host(host) => {
+                let players = host.connected_players();
+                trf(
+                    "hosting on {0}, {1} connected",
+                    &[&host.local_addr(), &players.len()],
+                )
+            }
+            TeamSync::Client(client) => {
+                let status = match client.is_connected() {
+                    true => tr("connected to host"),
+                    false => tr("connecting to host..."),
+                };
+                match client.rejected_count() {
+                    0 => status,
+                    rejected => trf(
+                        "{0}, {1} entries not counted by the host",
+                        &[&status, &fmt_int(rejected as u64)],
+                    ),
+                }
+            }
+        }
+    }
+
+    /// Sends the entry that was just added to the sync host, if this tracker is a client.
+    fn sync_last_entry(&mut self) {
+        self.sync_last_entries(1);
+    }
+
+    /// Sends the last `n` entries to the sync host, e.g. the queued ones added on unpausing.
+    fn sync_last_entries(&mut self, n: usize) {
+        if let TeamSync::Client(client) = &mut self.team_sync {
+            let entries: Vec<Entry> = self.marathon_log.entries().collect();
+            for &entry in &entries[entries.len().saturating_sub(n)..] {
+                client.send(entry);
+            }
+        }
     }
 
     fn clear_extra_lines(&mut self) {
@@ -251,33 +1374,27 @@ impl EguiTrackerApp {
     }
 
     fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let mut font_defs = FontDefinitions::default();
-        font_defs.font_data.insert(
-            "Recursive Mono".to_owned(),
-            FontData::from_static(include_bytes!("fonts/RecursiveMonoLnrSt-Bold.ttf")),
-        );
-        font_defs
-            .families
-            .get_mut(&FontFamily::Monospace)
-            .unwrap()
-            .insert(0, "Recursive Mono".to_owned());
-        font_defs.font_data.insert(
-            "Recursive Sans".to_owned(),
-            FontData::from_static(include_bytes!("fonts/RecursiveSansLnrSt-Bold.ttf")),
-        );
-        font_defs
-            .families
-            .get_mut(&FontFamily::Proportional)
-            .unwrap()
-            .insert(0, "Recursive Sans".to_owned());
-        cc.egui_ctx.set_fonts(font_defs);
-
-        let style = (*cc.egui_ctx.style()).clone();
-        cc.egui_ctx.set_style(custom_egui_styles(style));
+        let settings = Settings::load_or_default(SETTINGS_FILE);
+        if let Err(err) = set_language(settings.language) {
+            warn!("using the built-in translations: {err}");
+        }
+        let theme = Theme::from_source(&settings.theme).unwrap_or_else(|err| {
+            warn!("using the default theme: {err}");
+            Theme::default()
+        });
+        if let Err(err) = theme_style::apply_theme(&theme, &cc.egui_ctx) {
+            warn!("using the built-in fonts: {err}");
+        }
+        let mut marathon_log = MarathonLog::new(settings.duration_secs);
+        marathon_log.set_late_entry_policy(settings.late_entry_policy);
+        marathon_log.set_pause_policy(settings.pause_policy);
+        marathon_log.set_grace_period(settings.grace_secs, settings.grace_rule);
+        marathon_log.set_long_drought_secs(settings.drought_thresholds.alarm_mins * 60);
 
         Self {
-            is_started: false,
-            marathon_log: MarathonLog::new(TWENTY_FOUR_HOURS_IN_SECS),
+            screen: Screen::Start,
+            marathon_log,
+            replay: None,
             save_on_exit: false,
             score_input_txt: String::new(),
             file_name_txt: String::new(),
@@ -285,21 +1402,323 @@ impl EguiTrackerApp {
             mins_txt: String::new(),
             secs_txt: String::new(),
             headstart_5k_txt: String::new(),
+            players_txt: String::new(),
+            sync_addr_txt: "0.0.0.0:7878".to_owned(),
+            team_sync: TeamSync::Off,
+            merge_files_txt: String::new(),
+            merge_rebase: false,
+            split_at_txt: String::new(),
+            merge_report_txt: String::new(),
+            library_runs: Vec::new(),
+            library_query_txt: String::new(),
+            split_mins: HOUR_SECS / 60,
+            compare_file_txt: String::new(),
+            compare_log: None,
+            duration_txt: time_counter(settings.duration_secs),
+            grace_txt: settings.grace_secs.to_string(),
+            settings,
+            settings_status_txt: String::new(),
+            marathon_ended: false,
+            auto_save_txt: String::new(),
+            alert_tracker: AlertTracker::default(),
+            flashes: Vec::new(),
+            theme,
+            theme_files: theme_files(),
             err_state: AppErrState {
                 timer_paused: false,
+                entry_paused: false,
+                late_entry: false,
+                entry_rejected: false,
                 invalid_score: None,
                 time_err: None,
+                file_err: None,
+                sync_err: None,
             },
         }
     }
 }
 
-/// The string in invalid_score is the invalid score that was attempted to be added.
-/// The string in time_err is the error string.
+/// A choice of scoring rule, and the minimum score for the rules that have one. The log is
+/// recounted whenever the rule changes.
+fn show_scoring_rule_picker(ui: &mut Ui, marathon_log: &mut MarathonLog) {
+    let old_rule = marathon_log.scoring_rule();
+    let min_score = match old_rule {
+        ScoringRule::Threshold { min_score } | ScoringRule::Streak { min_score } => min_score,
+        ScoringRule::TotalPoints => 5000,
+    };
+    let mut rule = old_rule;
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_source("scoring_rule")
+            .selected_text(rule.name())
+            .show_ui(ui, |ui| {
+                let choices = [
+                    ScoringRule::Threshold { min_score },
+                    ScoringRule::TotalPoints,
+                    ScoringRule::Streak { min_score },
+                ];
+                for choice in choices {
+                    ui.selectable_value(&mut rule, choice, choice.name());
+                }
+            });
+        if let ScoringRule::Threshold { min_score } | ScoringRule::Streak { min_score } = &mut rule
+        {
+            ui.label(tr("at least"));
+            ui.add(egui::DragValue::new(min_score).range(0..=5000));
+        }
+    });
+    if rule != old_rule {
+        marathon_log.set_scoring_rule(rule);
+    }
+}
+
+/// Text fields for each detail of a run.
+fn show_metadata_editor(ui: &mut Ui, metadata: &mut RunMetadata) {
+    use egui::TextEdit;
+
+    egui::Grid::new("run_metadata")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label(tr("Player:"));
+            ui.add(TextEdit::singleline(&mut metadata.player));
+            ui.end_row();
+            ui.label(tr("Map:"));
+            ui.add(TextEdit::singleline(&mut metadata.map_name));
+            ui.end_row();
+            ui.label(tr("Map ID:"));
+            ui.add(TextEdit::singleline(&mut metadata.map_id));
+            ui.end_row();
+            ui.label(tr("Game mode:"));
+            egui::ComboBox::from_id_source("game_mode")
+                .selected_text(tr(&metadata.game_mode.to_string()))
+                .show_ui(ui, |ui| {
+                    for game_mode in GameMode::ALL {
+                        ui.selectable_value(
+                            &mut metadata.game_mode,
+                            game_mode,
+                            tr(&game_mode.to_string()),
+                        );
+                    }
+                });
+            ui.end_row();
+            ui.label(tr("Target:"));
+            ui.horizontal(|ui| {
+                let mut has_target = metadata.target.is_some();
+                if ui.checkbox(&mut has_target, "").changed() {
+                    metadata.target = has_target.then_some(0);
+                }
+                if let Some(target) = &mut metadata.target {
+                    ui.add(egui::DragValue::new(target));
+                }
+            });
+            ui.end_row();
+            ui.label(tr("Rules:"));
+            ui.add(TextEdit::singleline(&mut metadata.rules));
+            ui.end_row();
+            ui.label(tr("Started:"));
+            ui.label(metadata.started_at_txt());
+            ui.end_row();
+            ui.label(tr("Setup:"));
+            ui.add(TextEdit::singleline(&mut metadata.setup));
+            ui.end_row();
+        });
+    ui.label(tr("Notes:"));
+    ui.add(TextEdit::multiline(&mut metadata.notes).desired_rows(3));
+}
+
+/// Shows the numbers from `snapshot` in two columns, with a button under each one. The left
+/// button is given with whether it can be clicked.
+fn show_snapshot(
+    ui: &mut Ui,
+    snapshot: &Snapshot,
+    target: Option<u32>,
+    thresholds: &DroughtThresholds,
+    theme: &Theme,
+    (left_btn_txt, left_btn_enabled): (&str, bool),
+    right_btn_txt: &str,
+) -> (Response, Response) {
+    let rule = snapshot.scoring_rule;
+    let hit_name = rule.hit_name();
+    let count_label = rule.count_label();
+    let time_left = snapshot.time_left_secs();
+    let countdown = time_counter(time_left);
+    let spoken_countdown = trf("Time left: {0}", &[&spoken_duration(time_left)]);
+
+    // the drought level is marked with signs too, not only with color
+    let (time_since_5k, spoken_since_5k) = match snapshot.time_since_last_hit {
+        Some(secs) => {
+            let (colour, marker, spoken_level) = match thresholds.level(secs) {
+                DroughtLevel::Short => (theme.colours.good, "", "Last {0}: {1} ago"),
+                DroughtLevel::Warning => (
+                    theme.colours.warning,
+                    " ⚠",
+                    "Last {0}: {1} ago, long drought",
+                ),
+                DroughtLevel::Alarm => (
+                    theme.colours.error,
+                    " ⚠⚠",
+                    "Last {0}: {1} ago, very long drought",
+                ),
+            };
+            let txt = format!("{}{marker}", time_counter(secs));
+            let spoken = trf(spoken_level, &[&hit_name, &spoken_duration(secs)]);
+            (
+                RichText::new(txt).color(theme_style::colour(colour)),
+                spoken,
+            )
+        }
+        None => (RichText::new(""), trf("No {0} yet", &[&hit_name])),
+    };
+    let (longest_drought, spoken_longest) =
+        match (theme.panels.longest_drought, snapshot.longest_drought_secs) {
+            (true, Some(secs)) => (
+                trf("longest {0}", &[&time_counter(secs)]),
+                trf("Longest drought: {0}", &[&spoken_duration(secs)]),
+            ),
+            _ => ("".to_owned(), "".to_owned()),
+        };
+
+    let behind_target = matches!(
+        (snapshot.estimated_pace, target),
+        (Some(pace), Some(target)) if pace < target
+    );
+    let (estimated_pace, spoken_pace) = match (snapshot.estimated_pace, target) {
+        (Some(pace), Some(target)) if behind_target => (
+            RichText::new(format!("{} ▼", fmt_int(pace)))
+                .color(theme_style::colour(theme.colours.warning)),
+            trf(
+                "Pace: {0}, behind the target of {1}",
+                &[&fmt_int(pace), &fmt_int(target)],
+            ),
+        ),
+        (Some(pace), _) => (
+            RichText::new(fmt_int(pace)),
+            trf("Pace: {0}", &[&fmt_int(pace)]),
+        ),
+        (None, _) => (RichText::new(""), tr("Pace: not known yet")),
+    };
+
+    ui.horizontal_top(|ui| {
+        let left_btn = ui
+            .vertical(|ui| {
+                ui.heading(tr("Time left:"));
+                metric_label(ui, countdown, &spoken_countdown);
+                ui.label("");
+                ui.heading(trf("Last {0}:", &[&hit_name]));
+                metric_label(ui, time_since_5k, &spoken_since_5k);
+                metric_label(ui, RichText::new(longest_drought).small(), &spoken_longest);
+
+                ui.label("");
+                ui.add_enabled(left_btn_enabled, Button::new(left_btn_txt))
+            })
+            .inner;
+        let right_btn = ui
+            .vertical(|ui| {
+                ui.heading(format!("{count_label}:"));
+                let count = fmt_int(snapshot.count);
+                metric_label(ui, count.as_str(), &format!("{count_label}: {count}"));
+                ui.label("");
+                ui.heading(tr("Pace:"));
+                metric_label(ui, estimated_pace, &spoken_pace);
+
+                ui.label("");
+                ui.label("");
+                ui.add(Button::new(right_btn_txt))
+            })
+            .inner;
+        (left_btn, right_btn)
+    })
+    .inner
+}
+
+/// A label that screen readers read as `spoken`, so a bare number is read out with what it is.
+fn metric_label(ui: &mut Ui, text: impl Into<WidgetText>, spoken: &str) -> Response {
+    let response = ui.label(text);
+    response.widget_info(|| WidgetInfo::labeled(WidgetType::Label, true, spoken));
+    response
+}
+
+/// Error text, marked with a sign as well as the theme's error color. Nothing is shown for an
+/// empty text.
+fn error_label(ui: &mut Ui, text: &str, theme: &Theme) {
+    if !text.is_empty() {
+        ui.label(
+            RichText::new(format!("⚠ {text}"))
+                .color(theme_style::colour(theme.colours.error))
+                .small(),
+        );
+    }
+}
+
+/// A banner across the top of the tracker while the timer is paused, saying what happens to
+/// entries added now.
+fn show_paused_banner(ui: &mut Ui, policy: PausePolicy, queued: usize, theme: &Theme) {
+    let detail = match policy {
+        PausePolicy::Reject => tr("Entries are not added until you unpause"),
+        PausePolicy::Queue => trf(
+            "Entries are added when you unpause, {0} waiting",
+            &[&queued],
+        ),
+        PausePolicy::AcceptAtPause => tr("Entries are added at the pause time"),
+    };
+    // the warning colour is made to be read on the theme's background, so it's used for the
+    // text and outline rather than as a fill
+    let warning = theme_style::colour(theme.colours.warning);
+    egui::Frame::none()
+        .stroke(egui::Stroke::new(2.0, warning))
+        .inner_margin(8.0)
+        .rounding(4.0)
+        .show(ui, |ui| {
+            ui.set_width(ui.available_width());
+            let heading = RichText::new(tr("⏸ PAUSED"))
+                .heading()
+                .strong()
+                .color(warning);
+            metric_label(ui, heading, &trf("Timer paused. {0}", &[&detail]));
+            ui.label(RichText::new(detail).color(warning));
+        });
+}
+
+/// The alerts on screen, newest first.
+fn show_flashes(ui: &mut Ui, flashes: &[(Alert, Instant)], snapshot: &Snapshot, theme: &Theme) {
+    let rule = snapshot.scoring_rule;
+    for (alert, _) in flashes.iter().rev() {
+        let (colour, marker) = match alert.is_warning() {
+            true => (theme.colours.error, "⚠"),
+            false => (theme.colours.good, "✔"),
+        };
+        let message = alert.message(&rule.hit_name(), &rule.count_label());
+        let txt = RichText::new(format!("{marker} {message}"));
+        metric_label(
+            ui,
+            txt.heading().strong().color(theme_style::colour(colour)),
+            &message,
+        );
+    }
+}
+
+fn log_round_details(round_result: &RoundResult) {
+    if let Some(round) = round_result.round {
+        let out_of = round.out_of.map_or("?".to_owned(), |n| n.to_string());
+        info!("pasted result is from round {}/{}", round.number, out_of);
+    }
+    if let Some(distance) = round_result.distance {
+        info!("pasted guess was {:.0} m away", distance.in_metres());
+    }
+    if let Some(secs) = round_result.time_taken_secs {
+        info!("pasted round took {secs} seconds");
+    }
+}
+
+/// invalid_score is either a score that couldn't be parsed, or one that didn't fit the entry.
 struct AppErrState {
     timer_paused: bool,
-    invalid_score: Option<String>,
-    time_err: Option<String>,
+    entry_paused: bool,
+    late_entry: bool,
+    entry_rejected: bool,
+    invalid_score: Option<TrackerError>,
+    time_err: Option<ClockError>,
+    file_err: Option<PersistError>,
+    sync_err: Option<std::io::Error>,
 }
 
 impl AppErrState {
@@ -307,50 +1726,36 @@ impl AppErrState {
         let mut err_display_txt = String::new();
 
         if self.timer_paused {
-            err_display_txt += "entries should NOT be added while the timer is paused!";
+            err_display_txt += &tr("entries should NOT be added while the timer is paused!");
+        }
+        if self.entry_paused {
+            err_display_txt += "\r\n";
+            err_display_txt += &tr("the timer is paused, the entry was not added");
+        }
+        if self.late_entry {
+            err_display_txt += "\r\n";
+            err_display_txt += &tr("the marathon is over, the entry was marked as late");
         }
-        if let Some(score_string) = &self.invalid_score {
-            err_display_txt += "\r\ninvalid score: ";
-            err_display_txt += score_string;
+        if self.entry_rejected {
+            err_display_txt += "\r\n";
+            err_display_txt += &tr("the marathon is over, the entry was not added");
         }
-        if let Some(time_err_string) = &self.time_err {
-            err_display_txt += "\r\nerror getting time: ";
-            err_display_txt += time_err_string;
+        if let Some(score_err) = &self.invalid_score {
+            err_display_txt += "\r\n";
+            err_display_txt += &score_err.to_string();
+        }
+        if let Some(time_err) = self.time_err {
+            err_display_txt += "\r\n";
+            err_display_txt += &TrackerError::from(time_err).to_string();
         }
 
         err_display_txt
     }
 }
 
-fn custom_egui_styles(mut style: Style) -> Style {
-    use egui::Margin;
-
-    let mut spacing = style.spacing.clone();
-    let window_margin = 30.0;
-    spacing.window_margin = Margin {
-        left: window_margin,
-        right: window_margin,
-        top: window_margin,
-        bottom: window_margin,
-    };
-    spacing.item_spacing = Vec2 { x: 50.0, y: 2.0 };
-    style.spacing = spacing;
-
-    style.text_styles = [
-        (Heading, FontId::new(26.0, FontFamily::Proportional)),
-        (Body, FontId::new(26.0, FontFamily::Monospace)),
-        (Monospace, FontId::new(26.0, FontFamily::Monospace)),
-        (Button, FontId::new(20.0, FontFamily::Proportional)),
-        (Small, FontId::new(16.0, FontFamily::Proportional)),
-    ]
-    .into();
-
-    style
-}
-
 fn custom_native_opts(mut opts: NativeOptions) -> NativeOptions {
     let window_x = 350.0;
-    let window_y = 420.0;
+    let window_y = 560.0;
 
     use eframe::egui::IconData;
     opts.viewport = opts
@@ -1,166 +1,1143 @@
+use std::fmt;
+use std::fs::{self, File};
 use std::io::Write;
 use std::time::Instant;
-use std::fs::{self, File};
-use std::io;
 
-use log::{info, error};
-use serde::{Serialize, Deserialize};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{droughts, Drought};
+use crate::clock::{Clock, SystemClock};
+use crate::error::{ClockError, PersistError};
+use crate::games::{game_stats, group_games, Game, GameStats};
+use crate::metadata::RunMetadata;
+use crate::scoring::{ScoringRule, Tally};
+
+/// Logs are saved to the first of data0.ron, data1.ron, ... that doesn't exist yet.
+const SAVE_FILE_COUNT: u32 = 20;
+/// How many players a log can have, as entries store the player as a u8.
+pub const MAX_PLAYERS: usize = 256;
+
+fn save_file_name(num: u32) -> String {
+    "data".to_owned() + &num.to_string() + ".ron"
+}
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The entries of a log as they are saved to file. Entry `i` is made of the `i`th item of each list.
+#[derive(Debug, Default, Serialize, Deserialize)]
 struct LogEntries {
     scores: Vec<u16>,
     times: Vec<u32>,
+    #[serde(default)] // files saved before entry kinds existed only contain guesses
+    kinds: Vec<EntryKind>,
+    #[serde(default)] // files saved before players existed only have the first player
+    players: Vec<u8>,
+    #[serde(default)]
+    player_names: Vec<String>,
+    #[serde(default)] // files saved before games existed are grouped by round count
+    game_starts: Vec<bool>,
+    #[serde(default)] // files saved before the end of a marathon was detected
+    timings: Vec<EntryTiming>,
+    #[serde(default)]
+    handoffs: Vec<Handoff>,
+    #[serde(default)]
+    pauses: Vec<Pause>,
+    /// Droughts that went past the alarm threshold, in time order.
+    #[serde(default)]
+    long_droughts: Vec<Drought>,
+    #[serde(default)]
+    metadata: RunMetadata,
+    #[serde(default)] // files saved before scoring rules existed counted 5ks
+    scoring_rule: ScoringRule,
+    /// The length of the marathon. Files saved before it was saved use the length they're
+    /// loaded with.
+    #[serde(default)]
+    duration_secs: Option<u32>,
+    /// Entries added while paused under [`PausePolicy::Queue`], in the order they were added.
+    /// They are added to the other lists when the timer is resumed, after loading if need be.
+    #[serde(default)]
+    queued: Vec<Entry>,
 }
 
-pub(crate) struct MarathonLog {
-    pub(crate) marathon_duration_secs: u32, // this timer should not run for 136 years
-    pub(crate) current_epoch: Option<Instant>,
-    pub(crate) epoch_offset_secs: u32,
-    pub(crate) total_5ks: u16,
+impl LogEntries {
+    fn push(&mut self, entry: Entry) {
+        self.insert(self.scores.len(), entry);
+    }
+
+    fn insert(&mut self, i: usize, entry: Entry) {
+        self.scores.insert(i, entry.score);
+        self.times.insert(i, entry.time_secs);
+        self.kinds.insert(i, entry.kind);
+        self.players.insert(i, entry.player);
+        self.game_starts.insert(i, entry.starts_game);
+        self.timings.insert(i, entry.timing);
+        let min_player_count = usize::from(entry.player) + 1;
+        if self.player_names.len() < min_player_count {
+            self.player_names.resize(min_player_count, String::new());
+        }
+    }
+
+    fn get(&self, i: usize) -> Entry {
+        Entry {
+            score: self.scores[i],
+            time_secs: self.times[i],
+            kind: self.kinds[i],
+            player: self.players[i],
+            starts_game: self.game_starts[i],
+            timing: self.timings[i],
+        }
+    }
+
+    /// Fills in the lists that are missing from files saved by older versions of the tracker.
+    fn fill_missing_columns(&mut self) {
+        let len = self.scores.len();
+        if self.kinds.len() < len {
+            self.kinds.resize(len, EntryKind::Guess);
+        }
+        if self.players.len() < len {
+            self.players.resize(len, 0);
+        }
+        if self.game_starts.len() < len {
+            self.game_starts.resize(len, false);
+        }
+        if self.timings.len() < len {
+            self.timings.resize(len, EntryTiming::OnTime);
+        }
+        let handoff_players = self
+            .handoffs
+            .iter()
+            .flat_map(|handoff| [handoff.from, handoff.to]);
+        let max_player = self.players.iter().copied().chain(handoff_players).max();
+        let min_player_count = max_player.map_or(1, |max| usize::from(max) + 1);
+        if self.player_names.len() < min_player_count {
+            self.player_names.resize(min_player_count, String::new());
+        }
+    }
+}
+
+/// What happened in the round an entry was recorded for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EntryKind {
+    /// A guess was made, the score can be anything from 0 to 5000.
+    Guess,
+    /// The round timer ran out before a guess was made.
+    NoGuess,
+    /// The game was skipped or abandoned before the round was played.
+    Skipped,
+}
+
+impl fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EntryKind::Guess => "Guess",
+            EntryKind::NoGuess => "No guess",
+            EntryKind::Skipped => "Skipped",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Whether an entry was added before the end of the marathon.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EntryTiming {
+    #[default]
+    OnTime,
+    /// Added in the grace period after the end of the marathon, for finishing the round in
+    /// progress. Whether it counts depends on the [`GraceRule`] when it was added.
+    Grace { counted: bool },
+    /// Added after the end of the marathon. Late entries are kept, but not counted.
+    Late,
+}
+
+impl EntryTiming {
+    /// Whether the entry adds to the count.
+    pub fn counts(&self) -> bool {
+        match self {
+            EntryTiming::OnTime => true,
+            EntryTiming::Grace { counted } => *counted,
+            EntryTiming::Late => false,
+        }
+    }
+}
+
+/// Which entries added in the grace period count.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GraceRule {
+    CountAll,
+    /// Only the first entry counts, as that's the round that was in progress at the end.
+    #[default]
+    CountFirst,
+    /// Grace entries are kept, but none of them count.
+    CountNone,
+}
+
+impl GraceRule {
+    pub const ALL: [GraceRule; 3] = [
+        GraceRule::CountAll,
+        GraceRule::CountFirst,
+        GraceRule::CountNone,
+    ];
+}
+
+impl fmt::Display for GraceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GraceRule::CountAll => "Count all",
+            GraceRule::CountFirst => "Count the first",
+            GraceRule::CountNone => "Count none",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What happens to entries added after the end of the marathon.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LateEntryPolicy {
+    /// Added and counted like any other entry.
+    Accept,
+    /// Added, but marked as late so they aren't counted.
+    #[default]
+    Flag,
+    /// Not added at all.
+    Reject,
+}
+
+impl LateEntryPolicy {
+    pub const ALL: [LateEntryPolicy; 3] = [
+        LateEntryPolicy::Accept,
+        LateEntryPolicy::Flag,
+        LateEntryPolicy::Reject,
+    ];
+}
+
+impl fmt::Display for LateEntryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LateEntryPolicy::Accept => "Accept",
+            LateEntryPolicy::Flag => "Mark as late",
+            LateEntryPolicy::Reject => "Reject",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What happens to entries added while the timer is paused.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PausePolicy {
+    /// Not added at all.
+    #[default]
+    Reject,
+    /// Held back, and added when the timer is resumed.
+    Queue,
+    /// Added at the time the timer was paused at.
+    AcceptAtPause,
+}
+
+impl PausePolicy {
+    pub const ALL: [PausePolicy; 3] = [
+        PausePolicy::Reject,
+        PausePolicy::Queue,
+        PausePolicy::AcceptAtPause,
+    ];
+}
+
+impl fmt::Display for PausePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PausePolicy::Reject => "Reject",
+            PausePolicy::Queue => "Queue until unpaused",
+            PausePolicy::AcceptAtPause => "Add at the pause time",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A single entry of a log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub score: u16,
+    /// Seconds since the start of the marathon, not counting time spent paused.
+    pub time_secs: u32,
+    pub kind: EntryKind,
+    /// The index of the player that added the entry, 0 unless the log has several players.
+    pub player: u8,
+    /// Whether the entry is the first round of a new game, when that was marked explicitly.
+    pub starts_game: bool,
+    pub timing: EntryTiming,
+}
+
+/// When one player took over from another, in a log with several players.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Handoff {
+    pub time_secs: u32,
+    pub from: u8,
+    pub to: u8,
+}
+
+/// A stretch of time the timer was paused for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pause {
+    /// The marathon time the timer was paused at.
+    pub time_secs: u32,
+    /// How long the pause lasted in real time.
+    pub real_secs: u32,
+}
+
+/// The numbers for a single player in a log with several players.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerStats {
+    pub name: String,
+    /// The player's own count, under the log's scoring rule.
+    pub count: u32,
+    pub rounds: u32,
+    /// How long the player has been the active player for.
+    pub active_secs: u32,
+    /// Count per hour of being the active player, `None` until they've been active for a bit.
+    pub per_hour: Option<f64>,
+}
+
+/// The state of a marathon: its timer, and every entry added so far.
+///
+/// Time is measured as seconds since the start of the marathon. While the timer is running it's
+/// the time since `current_epoch` plus `epoch_offset_secs`, pausing folds the time since the
+/// epoch into the offset. The current time comes from a [`Clock`], which is the system clock
+/// unless the log was made with [`MarathonLog::with_clock`].
+pub struct MarathonLog<C: Clock = SystemClock> {
+    clock: C,
+    marathon_duration_secs: u32, // this timer should not run for 136 years
+    current_epoch: Option<Instant>,
+    epoch_offset_secs: u32,
+    /// When the timer was paused, so the pause can be recorded when it's resumed.
+    paused_at: Option<Instant>,
+    tally: Tally,
+    active_player: u8,
+    /// Whether the next entry added is the first round of a new game.
+    next_starts_game: bool,
+    late_entry_policy: LateEntryPolicy,
+    pause_policy: PausePolicy,
+    /// How long after the end entries are still taken, as grace entries.
+    grace_secs: u32,
+    grace_rule: GraceRule,
+    /// Whether [`MarathonLog::end`] was called, which also ends the grace period.
+    ended: bool,
+    /// How long a drought has to be to get recorded, 0 for never.
+    long_drought_secs: u32,
     log_entries: LogEntries,
 }
 
-pub(crate) enum AddEntryResult {
+/// The outcome of trying to add an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddEntryResult {
     Ok,
+    /// The entry was added, but at the time the timer was paused at.
     TimerPaused,
-    ImpossibleScore { score: u16 },
+    /// The timer is paused, so the entry was held back until it's resumed.
+    Queued,
+    /// The timer is paused, so the entry wasn't added.
+    Paused,
+    /// The entry was added in the grace period after the end of the marathon.
+    Grace,
+    /// The entry was added after the end of the marathon, and marked as late.
+    Late,
+    /// The marathon is over, so the entry wasn't added.
+    MarathonOver,
+    /// The score isn't possible for the kind of entry, so it wasn't added.
+    ImpossibleScore {
+        score: u16,
+    },
+}
+
+/// The numbers the tracker shows, at one point in a marathon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Snapshot {
+    pub elapsed_secs: u32,
+    pub duration_secs: u32,
+    pub scoring_rule: ScoringRule,
+    /// The count under `scoring_rule`, e.g. the number of 5ks.
+    pub count: u32,
+    pub estimated_pace: Option<u32>,
+    pub time_since_last_hit: Option<u32>,
+    /// The longest stretch without a hit so far, counting the one going on.
+    pub longest_drought_secs: Option<u32>,
+}
+
+impl Snapshot {
+    pub fn time_left_secs(&self) -> u32 {
+        self.duration_secs.saturating_sub(self.elapsed_secs)
+    }
 }
 
 impl MarathonLog {
-    pub(crate) fn new(duration: u32) -> Self {
+    /// Creates an empty, paused log for a marathon lasting `duration` seconds.
+    pub fn new(duration: u32) -> Self {
+        MarathonLog::with_clock(duration, SystemClock)
+    }
+
+    /// Creates a paused log with the entries saved in `path`. `duration` is the length of the
+    /// marathon if the file doesn't have it.
+    pub fn from_file(duration: u32, path: &str) -> Result<Self, PersistError> {
+        let mut marathon_log = MarathonLog::new(duration);
+        marathon_log.load_from_file(path)?;
+        Ok(marathon_log)
+    }
+
+    /// Creates a paused log that already has `entries` in it, e.g. for analysing a run.
+    pub fn from_entries(duration: u32, entries: impl IntoIterator<Item = Entry>) -> Self {
+        let mut marathon_log = MarathonLog::new(duration);
+        for entry in entries {
+            marathon_log.log_entries.push(entry);
+        }
+        marathon_log.tally = marathon_log.add_up_tally();
+        marathon_log
+    }
+}
+
+impl<C: Clock> MarathonLog<C> {
+    /// Creates an empty, paused log that gets the current time from `clock`.
+    pub fn with_clock(duration: u32, clock: C) -> Self {
         MarathonLog {
+            clock,
             marathon_duration_secs: duration,
             current_epoch: None,
             epoch_offset_secs: 0,
-            total_5ks: 0,
+            paused_at: None,
+            tally: Tally::default(),
+            active_player: 0,
+            next_starts_game: false,
+            late_entry_policy: LateEntryPolicy::default(),
+            pause_policy: PausePolicy::default(),
+            grace_secs: 0,
+            grace_rule: GraceRule::default(),
+            ended: false,
+            long_drought_secs: 0,
             log_entries: LogEntries {
-                scores: Vec::new(),
-                times: Vec::new(),
+                player_names: vec![String::new()],
+                duration_secs: Some(duration),
+                ..LogEntries::default()
             },
         }
     }
 
-    pub(crate) fn try_add_entry(&mut self, score: u16) -> AddEntryResult {
+    /// Adds a guess with any score up to 5000.
+    pub fn try_add_entry(&mut self, score: u16) -> AddEntryResult {
         if score > 5000 {
             return AddEntryResult::ImpossibleScore { score };
         }
+        self.add_entry_of_kind(score, EntryKind::Guess)
+    }
 
-        let mut res = AddEntryResult::Ok;
-        let time_since_epoch = match self.current_epoch {
-            Some(epoch) => epoch.elapsed().as_secs() as u32,
-            None => {
-                res = AddEntryResult::TimerPaused;
-                0
-            }
+    /// Adds a guess that wasn't a 5k, so a score of 5000 is rejected.
+    pub fn try_add_miss(&mut self, score: u16) -> AddEntryResult {
+        if score >= 5000 {
+            return AddEntryResult::ImpossibleScore { score };
+        }
+        self.add_entry_of_kind(score, EntryKind::Guess)
+    }
+
+    /// Adds a round where the timer ran out without a guess, it is recorded with a score of 0.
+    pub fn add_no_guess(&mut self) -> AddEntryResult {
+        self.add_entry_of_kind(0, EntryKind::NoGuess)
+    }
+
+    /// Adds a skipped or abandoned game, it is recorded with a score of 0.
+    pub fn add_skipped(&mut self) -> AddEntryResult {
+        self.add_entry_of_kind(0, EntryKind::Skipped)
+    }
+
+    fn add_entry_of_kind(&mut self, score: u16, kind: EntryKind) -> AddEntryResult {
+        let now_secs = self.unclamped_elapsed_secs().unwrap_or(u32::MAX);
+        let (timing, res) = match self.timing_at(now_secs, score, kind) {
+            Ok(timing) => timing,
+            Err(res) => return res,
         };
-        let time_seconds = time_since_epoch + self.epoch_offset_secs;
+        let time_seconds = self.elapsed_secs().unwrap_or(u32::MAX);
 
-        self.log_entries.scores.push(score);
-        self.log_entries.times.push(time_seconds);
-        if score == 5000 {
-            self.total_5ks += 1;
+        let entry = Entry {
+            score,
+            time_secs: time_seconds,
+            kind,
+            player: self.active_player,
+            starts_game: std::mem::take(&mut self.next_starts_game),
+            timing,
+        };
+        if res == AddEntryResult::Queued {
+            info!("queued entry: {kind:?} with score {score} until the timer is resumed");
+            self.log_entries.queued.push(entry);
+        } else {
+            self.push_entry(entry);
         }
+        res
+    }
 
-        info!("added entry: score {score} at time {time_seconds}");
+    /// Adds an entry that was recorded by another tracker, e.g. one synced from a client. The
+    /// entry keeps its own time, but the pause policy, grace rule and late entry policy of this
+    /// log decide whether and how it's added, as they do for entries added here.
+    pub fn add_synced_entry(&mut self, entry: Entry) -> AddEntryResult {
+        let Entry { score, kind, .. } = entry;
+        let (timing, res) = match self.timing_at(entry.time_secs, score, kind) {
+            Ok(timing) => timing,
+            Err(res) => return res,
+        };
+        let entry = Entry { timing, ..entry };
+        match res {
+            AddEntryResult::Queued => {
+                info!("queued entry: {kind:?} with score {score} until the timer is resumed");
+                self.log_entries.queued.push(entry);
+            }
+            AddEntryResult::TimerPaused => self.push_entry(Entry {
+                time_secs: self.epoch_offset_secs.min(entry.time_secs),
+                ..entry
+            }),
+            _ => self.push_entry(entry),
+        }
         res
     }
 
-    pub(crate) fn estimate_pace(&self) -> Option<u32> {
-        let time_since_epoch = match self.current_epoch {
-            Some(epoch) => epoch.elapsed().as_secs() as u32,
-            None => 0,
+    /// How an entry at `time_secs` on the marathon clock is added, or why it isn't. Before the
+    /// end this depends on the pause policy, after it on the grace rule and late entry policy.
+    fn timing_at(
+        &self,
+        time_secs: u32,
+        score: u16,
+        kind: EntryKind,
+    ) -> Result<(EntryTiming, AddEntryResult), AddEntryResult> {
+        let past_end_secs = time_secs.checked_sub(self.marathon_duration_secs);
+        let timing = match past_end_secs {
+            // an ended marathon is paused too, but entries from before the end still count
+            None => match (self.is_paused() && !self.is_over(), self.pause_policy) {
+                (false, _) => (EntryTiming::OnTime, AddEntryResult::Ok),
+                (true, PausePolicy::AcceptAtPause) => {
+                    (EntryTiming::OnTime, AddEntryResult::TimerPaused)
+                }
+                (true, PausePolicy::Queue) => (EntryTiming::OnTime, AddEntryResult::Queued),
+                (true, PausePolicy::Reject) => {
+                    info!("rejected entry: {kind:?} with score {score}, the timer is paused");
+                    return Err(AddEntryResult::Paused);
+                }
+            },
+            Some(past_end_secs) if !self.ended && past_end_secs < self.grace_secs => {
+                let counted = match self.grace_rule {
+                    GraceRule::CountAll => true,
+                    GraceRule::CountFirst => !self
+                        .log_entries
+                        .timings
+                        .iter()
+                        .any(|timing| matches!(timing, EntryTiming::Grace { .. })),
+                    GraceRule::CountNone => false,
+                };
+                (EntryTiming::Grace { counted }, AddEntryResult::Grace)
+            }
+            Some(_) => match self.late_entry_policy {
+                LateEntryPolicy::Accept => (EntryTiming::OnTime, AddEntryResult::Ok),
+                LateEntryPolicy::Flag => (EntryTiming::Late, AddEntryResult::Late),
+                LateEntryPolicy::Reject => {
+                    info!("rejected entry: {kind:?} with score {score}, the marathon is over");
+                    return Err(AddEntryResult::MarathonOver);
+                }
+            },
         };
-        let current_time = self.epoch_offset_secs + time_since_epoch;
-        if current_time == 0 || self.total_5ks == 0 {
+        Ok(timing)
+    }
+
+    /// Adds `entry` in time order, which is at the end unless it was recorded somewhere else.
+    fn push_entry(&mut self, entry: Entry) {
+        if self.log_entries.scoring_rule.is_hit(&entry) && entry.timing.counts() {
+            self.record_long_drought(entry.time_secs);
+        }
+        self.insert_entry(entry);
+
+        let Entry {
+            kind,
+            score,
+            time_secs,
+            ..
+        } = entry;
+        info!("added entry: {kind:?} with score {score} at time {time_secs}");
+    }
+
+    /// Starts the timer, or restarts it after a pause, adding the entries queued while it was
+    /// paused at the time it's resumed at. Returns how many were added, and does nothing if the
+    /// timer is already running.
+    pub fn resume(&mut self) -> usize {
+        if self.current_epoch.is_some() {
+            return 0;
+        }
+        let now = self.clock.now();
+        if let Some(paused_at) = self.paused_at.take() {
+            let real_secs = now.saturating_duration_since(paused_at).as_secs();
+            self.log_entries.pauses.push(Pause {
+                time_secs: self.epoch_offset_secs,
+                real_secs: u32::try_from(real_secs).unwrap_or(u32::MAX),
+            });
+        }
+        self.current_epoch = Some(now);
+        let queued = std::mem::take(&mut self.log_entries.queued);
+        let resumed_at = self.epoch_offset_secs.min(self.marathon_duration_secs);
+        for entry in &queued {
+            self.push_entry(Entry {
+                time_secs: resumed_at,
+                ..*entry
+            });
+        }
+        queued.len()
+    }
+
+    /// Stops the timer, keeping the time that has passed so far. The pause is recorded in the
+    /// log when the timer is resumed.
+    pub fn pause(&mut self) -> Result<(), ClockError> {
+        let time_since_epoch = self.time_since_epoch()?;
+        self.epoch_offset_secs += time_since_epoch;
+        if self.current_epoch.take().is_some() {
+            self.paused_at = Some(self.clock.now());
+        }
+        Ok(())
+    }
+
+    /// Whether the timer is stopped by a pause. A marathon that has been ended isn't paused,
+    /// as it can't be resumed.
+    pub fn is_paused(&self) -> bool {
+        self.current_epoch.is_none() && !self.ended
+    }
+
+    /// Sets how far into the marathon the timer is, for runs that didn't start in this tracker.
+    /// This should be called while the timer is paused.
+    pub fn set_elapsed_secs(&mut self, secs: u32) {
+        self.epoch_offset_secs = secs;
+    }
+
+    /// Seconds since the start of the marathon, not counting time spent paused. The clock stops
+    /// at the end of the marathon.
+    pub fn elapsed_secs(&self) -> Result<u32, ClockError> {
+        Ok(self
+            .unclamped_elapsed_secs()?
+            .min(self.marathon_duration_secs))
+    }
+
+    fn unclamped_elapsed_secs(&self) -> Result<u32, ClockError> {
+        Ok(self
+            .epoch_offset_secs
+            .saturating_add(self.time_since_epoch()?))
+    }
+
+    /// Whether the time is up. Entries added from now on are handled by the late entry policy.
+    pub fn is_over(&self) -> bool {
+        self.unclamped_elapsed_secs().unwrap_or(u32::MAX) >= self.marathon_duration_secs
+    }
+
+    /// Whether the time is up, but entries are still taken as grace entries. The grace period
+    /// runs on marathon time, so it stops while the timer is paused.
+    pub fn in_grace_period(&self) -> bool {
+        self.grace_secs_left().is_some()
+    }
+
+    /// How long is left of the grace period, or `None` outside of it.
+    pub fn grace_secs_left(&self) -> Option<u32> {
+        if self.ended || !self.is_over() {
             return None;
-        };
+        }
+        let past_end = self
+            .unclamped_elapsed_secs()
+            .ok()?
+            .saturating_sub(self.marathon_duration_secs);
+        self.grace_secs
+            .checked_sub(past_end)
+            .filter(|&left| left > 0)
+    }
+
+    /// Stops the timer at the end of the marathon, and ends the grace period. Unlike
+    /// [`MarathonLog::pause`], this isn't recorded as a pause.
+    pub fn end(&mut self) {
+        self.epoch_offset_secs = self.elapsed_secs().unwrap_or(self.marathon_duration_secs);
+        self.current_epoch = None;
+        self.paused_at = None;
+        if !self.ended {
+            self.record_long_drought(self.epoch_offset_secs);
+        }
+        self.ended = true;
+    }
+
+    /// Records the drought running up to `time_secs` if it's gone past the alarm threshold.
+    fn record_long_drought(&mut self, time_secs: u32) {
+        if self.long_drought_secs == 0 {
+            return;
+        }
+        let rule = self.log_entries.scoring_rule;
+        let before = self
+            .entries()
+            .take_while(|entry| entry.time_secs <= time_secs);
+        let current = droughts(before, &rule, time_secs).pop();
+        if let Some(drought) = current.filter(|d| d.end_secs == time_secs) {
+            if drought.secs() >= self.long_drought_secs {
+                info!("recorded a drought of {} seconds", drought.secs());
+                self.insert_long_drought(drought);
+            }
+        }
+    }
+
+    /// Sets how long a drought has to be to get recorded in the log, 0 for never.
+    pub fn set_long_drought_secs(&mut self, secs: u32) {
+        self.long_drought_secs = secs;
+    }
+
+    /// The droughts that went past the alarm threshold while the log was tracked.
+    pub fn long_droughts(&self) -> &[Drought] {
+        &self.log_entries.long_droughts
+    }
+
+    /// Records a long drought, keeping them in time order.
+    pub fn insert_long_drought(&mut self, drought: Drought) {
+        let long_droughts = &mut self.log_entries.long_droughts;
+        let i = long_droughts.partition_point(|other| other.start_secs <= drought.start_secs);
+        long_droughts.insert(i, drought);
+    }
+
+    pub fn duration_secs(&self) -> u32 {
+        self.marathon_duration_secs
+    }
+
+    pub fn set_duration_secs(&mut self, duration: u32) {
+        self.marathon_duration_secs = duration;
+        self.log_entries.duration_secs = Some(duration);
+    }
+
+    pub fn late_entry_policy(&self) -> LateEntryPolicy {
+        self.late_entry_policy
+    }
+
+    pub fn set_late_entry_policy(&mut self, policy: LateEntryPolicy) {
+        self.late_entry_policy = policy;
+    }
+
+    pub fn pause_policy(&self) -> PausePolicy {
+        self.pause_policy
+    }
+
+    pub fn set_pause_policy(&mut self, policy: PausePolicy) {
+        self.pause_policy = policy;
+    }
+
+    /// The entries waiting for the timer to be resumed, see [`PausePolicy::Queue`].
+    pub fn queued_entries(&self) -> &[Entry] {
+        &self.log_entries.queued
+    }
+
+    /// Sets how long after the end entries are still taken, and which of them count.
+    pub fn set_grace_period(&mut self, grace_secs: u32, rule: GraceRule) {
+        self.grace_secs = grace_secs;
+        self.grace_rule = rule;
+    }
 
-        let pace = f64::from(self.total_5ks) / f64::from(current_time);
-        let remaining_duration = f64::from(self.marathon_duration_secs - current_time);
-        let remaining_estimate = pace * remaining_duration;
-        let remaining_estimate = remaining_estimate.trunc() as u32;
-        Some(u32::from(self.total_5ks) + remaining_estimate)
+    /// The count under the scoring rule, e.g. the number of 5ks.
+    pub fn count(&self) -> u32 {
+        self.tally.count
     }
 
-    pub(crate) fn time_since_last_5k(&self) -> Option<u32> {
-        let time_since_epoch = match self.current_epoch {
-            Some(epoch) => epoch.elapsed().as_secs() as u32,
-            None => 0,
+    pub fn scoring_rule(&self) -> ScoringRule {
+        self.log_entries.scoring_rule
+    }
+
+    /// Makes the next entry the first round of a new game, even if the current game isn't over.
+    pub fn start_new_game(&mut self) {
+        self.next_starts_game = true;
+    }
+
+    /// The rounds grouped into games, see [`crate::games`].
+    pub fn games(&self) -> Vec<Game> {
+        group_games(self.entries())
+    }
+
+    pub fn game_stats(&self) -> GameStats {
+        game_stats(self.entries(), &self.log_entries.scoring_rule)
+    }
+
+    /// The game being played now, which is `None` before the first round and after a new game
+    /// was started or a game was skipped.
+    pub fn current_game(&self) -> Option<Game> {
+        let last_entry = self.last_entry()?;
+        if self.next_starts_game || last_entry.kind == EntryKind::Skipped {
+            return None;
+        }
+        self.games().pop().filter(|game| !game.is_complete())
+    }
+
+    /// Changes what the log counts, recounting the entries added so far.
+    pub fn set_scoring_rule(&mut self, scoring_rule: ScoringRule) {
+        self.log_entries.scoring_rule = scoring_rule;
+        self.tally = self.add_up_tally();
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = Entry> + '_ {
+        (0..self.log_entries.scores.len()).map(|i| self.log_entries.get(i))
+    }
+
+    pub fn last_entry(&self) -> Option<Entry> {
+        self.log_entries
+            .scores
+            .len()
+            .checked_sub(1)
+            .map(|i| self.log_entries.get(i))
+    }
+
+    /// The marathon time of the last entry, which is where a saved run is taken to end.
+    pub fn played_secs(&self) -> u32 {
+        self.last_entry().map_or(0, |entry| entry.time_secs)
+    }
+
+    /// Adds an entry that was recorded somewhere else, keeping the entries in time order. The
+    /// entry is taken as it is, for entries coming in while tracking see
+    /// [`MarathonLog::add_synced_entry`].
+    pub fn insert_entry(&mut self, entry: Entry) {
+        let i = self
+            .log_entries
+            .times
+            .partition_point(|&time| time <= entry.time_secs);
+        self.log_entries.insert(i, entry);
+        if i + 1 == self.log_entries.scores.len() {
+            self.tally.add(&self.log_entries.scoring_rule, &entry);
+        } else {
+            // a streak can depend on the order of entries, so count them again
+            self.tally = self.add_up_tally();
+        }
+    }
+
+    /// The index of the player called `name`, adding them if there's no such player yet. Fails
+    /// if the log already has [`MAX_PLAYERS`] players.
+    pub fn find_or_add_player(&mut self, name: &str) -> Result<u8, PersistError> {
+        let names = &mut self.log_entries.player_names;
+        if let Some(i) = names.iter().position(|player_name| player_name == name) {
+            return Ok(i as u8);
+        }
+        if names.len() >= MAX_PLAYERS {
+            return Err(PersistError::TooManyPlayers {
+                name: name.to_owned(),
+            });
+        }
+        names.push(name.to_owned());
+        Ok((names.len() - 1) as u8)
+    }
+
+    /// Replaces the players with `names`, for a log that is shared by several players. The first
+    /// player is the active one, and names past [`MAX_PLAYERS`] are dropped.
+    pub fn set_player_names(&mut self, mut names: Vec<String>) {
+        let min_player_count = self.log_entries.player_names.len().max(1);
+        names.truncate(MAX_PLAYERS);
+        self.log_entries.player_names = names;
+        if self.log_entries.player_names.len() < min_player_count {
+            self.log_entries
+                .player_names
+                .resize(min_player_count, String::new());
+        }
+        self.active_player = 0;
+    }
+
+    /// The name of each player, which can be empty if it was never set.
+    pub fn player_names(&self) -> &[String] {
+        &self.log_entries.player_names
+    }
+
+    /// The name of a player, or "Player N" if it was never set.
+    pub fn player_name(&self, player: u8) -> String {
+        match self.log_entries.player_names.get(usize::from(player)) {
+            Some(name) if !name.is_empty() => name.clone(),
+            _ => format!("Player {}", u16::from(player) + 1),
+        }
+    }
+
+    pub fn active_player(&self) -> u8 {
+        self.active_player
+    }
+
+    /// Makes `player` the one that new entries are added for, recording a handoff at the current
+    /// time. Players that don't exist are ignored.
+    pub fn set_active_player(&mut self, player: u8) {
+        if player == self.active_player || usize::from(player) >= self.player_names().len() {
+            return;
+        }
+        let handoff = Handoff {
+            time_secs: self.elapsed_secs().unwrap_or(u32::MAX),
+            from: self.active_player,
+            to: player,
         };
-        let current_time = self.epoch_offset_secs + time_since_epoch;
+        info!(
+            "handoff from {} to {}",
+            self.player_name(handoff.from),
+            self.player_name(handoff.to)
+        );
+        self.log_entries.handoffs.push(handoff);
+        self.active_player = player;
+    }
+
+    /// The details of the run, which are saved with the entries.
+    pub fn metadata(&self) -> &RunMetadata {
+        &self.log_entries.metadata
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut RunMetadata {
+        &mut self.log_entries.metadata
+    }
+
+    pub fn handoffs(&self) -> &[Handoff] {
+        &self.log_entries.handoffs
+    }
+
+    /// Records a handoff that happened somewhere else, keeping the handoffs in time order.
+    pub fn insert_handoff(&mut self, handoff: Handoff) {
+        let handoffs = &mut self.log_entries.handoffs;
+        let i = handoffs.partition_point(|other| other.time_secs <= handoff.time_secs);
+        handoffs.insert(i, handoff);
+        self.log_entries.fill_missing_columns();
+    }
+
+    /// The pauses so far, not counting one that's still going.
+    pub fn pauses(&self) -> &[Pause] {
+        &self.log_entries.pauses
+    }
+
+    /// Records a pause that happened somewhere else, keeping the pauses in time order.
+    pub fn insert_pause(&mut self, pause: Pause) {
+        let pauses = &mut self.log_entries.pauses;
+        let i = pauses.partition_point(|other| other.time_secs <= pause.time_secs);
+        pauses.insert(i, pause);
+    }
+
+    /// The numbers for each player, in the same order as [`MarathonLog::player_names`].
+    pub fn player_stats(&self) -> Vec<PlayerStats> {
+        let current_time = self.elapsed_secs().unwrap_or(0);
+        let player_count = self.player_names().len();
+        let mut active_secs = vec![0u32; player_count];
+        let mut stint_start = 0;
+        let mut stint_player = self.log_entries.handoffs.first().map_or(0, |h| h.from);
+        for handoff in &self.log_entries.handoffs {
+            let stint_end = handoff.time_secs.min(current_time);
+            active_secs[usize::from(stint_player)] += stint_end.saturating_sub(stint_start);
+            stint_start = stint_end;
+            stint_player = handoff.to;
+        }
+        active_secs[usize::from(stint_player)] += current_time.saturating_sub(stint_start);
+
+        (0..player_count)
+            .map(|i| {
+                let player = i as u8;
+                let entries: Vec<Entry> = self
+                    .entries()
+                    .filter(|entry| entry.player == player)
+                    .collect();
+                let count = self.log_entries.scoring_rule.count(entries.iter().copied());
+                let per_hour = match active_secs[i] {
+                    0 => None,
+                    secs => Some(f64::from(count) * 3600.0 / f64::from(secs)),
+                };
+                PlayerStats {
+                    name: self.player_name(player),
+                    count,
+                    rounds: entries.len() as u32,
+                    active_secs: active_secs[i],
+                    per_hour,
+                }
+            })
+            .collect()
+    }
+
+    /// Adds 5ks at the current time until the count is at least `count`, for runs that were
+    /// partly tracked somewhere else. For total points the last one can have fewer points, so
+    /// that the count comes out exact. Returns how many entries were added.
+    ///
+    /// The entries always count, even if the timer is paused or the marathon is over, as they
+    /// were played before the tracker took over.
+    pub fn add_headstart(&mut self, count: u32) -> u32 {
+        let time_secs = self.elapsed_secs().unwrap_or(self.marathon_duration_secs);
+        let mut added = 0;
+        while self.count() < count {
+            let score = match self.log_entries.scoring_rule {
+                ScoringRule::TotalPoints => (count - self.count()).min(5000) as u16,
+                _ => 5000,
+            };
+            let count_before = self.count();
+            let entry = Entry {
+                score,
+                time_secs,
+                kind: EntryKind::Guess,
+                player: self.active_player,
+                starts_game: std::mem::take(&mut self.next_starts_game),
+                timing: EntryTiming::OnTime,
+            };
+            self.push_entry(entry);
+            added += 1;
+            if self.count() == count_before {
+                break; // a 5k doesn't count under this rule
+            }
+        }
+        added
+    }
+
+    /// Seconds since the current epoch, or 0 if the timer is paused.
+    pub fn time_since_epoch(&self) -> Result<u32, ClockError> {
+        match self.current_epoch {
+            Some(epoch) => {
+                let elapsed_secs = self.clock.now().saturating_duration_since(epoch).as_secs();
+                u32::try_from(elapsed_secs).map_err(|_| ClockError::Overflow { elapsed_secs })
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Estimates what the count will be at the end of the marathon, if the rate so far is kept
+    /// up. Returns `None` until the count is above 0, or if the scoring rule can't be projected.
+    pub fn estimate_pace(&self) -> Option<u32> {
+        let current_time = self.elapsed_secs().ok()?;
+        self.estimate_pace_at(self.tally.count, current_time)
+    }
+
+    fn estimate_pace_at(&self, count: u32, time_secs: u32) -> Option<u32> {
+        if time_secs == 0 || count == 0 || !self.log_entries.scoring_rule.is_projectable() {
+            return None;
+        }
+        Some(project_count(count, time_secs, self.marathon_duration_secs))
+    }
+
+    /// Seconds since the most recent hit (a 5k, unless the scoring rule says otherwise), or
+    /// `None` if there hasn't been one.
+    pub fn time_since_last_hit(&self) -> Option<u32> {
+        let current_time = self.elapsed_secs().ok()?;
+        self.time_since_last_hit_at(current_time)
+    }
+
+    fn time_since_last_hit_at(&self, time_secs: u32) -> Option<u32> {
         assert_eq!(self.log_entries.scores.len(), self.log_entries.times.len());
+        let rule = self.log_entries.scoring_rule;
         for i in (0..self.log_entries.times.len()).rev() {
-            if self.log_entries.scores[i] == 5000 {
-                return Some(current_time - self.log_entries.times[i]);
+            let entry = self.log_entries.get(i);
+            if rule.is_hit(&entry) && entry.timing.counts() && entry.time_secs <= time_secs {
+                return Some(time_secs - entry.time_secs);
             }
         }
         None
     }
 
+    /// What the tracker should show right now.
+    pub fn snapshot(&self) -> Result<Snapshot, ClockError> {
+        Ok(Snapshot {
+            elapsed_secs: self.elapsed_secs()?,
+            duration_secs: self.marathon_duration_secs,
+            scoring_rule: self.log_entries.scoring_rule,
+            count: self.tally.count,
+            estimated_pace: self.estimate_pace(),
+            time_since_last_hit: self.time_since_last_hit(),
+            longest_drought_secs: self.longest_drought_at(self.elapsed_secs()?),
+        })
+    }
+
+    /// What the tracker would have shown at `time_secs` into the marathon, only counting entries
+    /// added up to then.
+    pub fn snapshot_at(&self, time_secs: u32) -> Snapshot {
+        let rule = self.log_entries.scoring_rule;
+        let count = rule.count(
+            self.entries()
+                .take_while(|entry| entry.time_secs <= time_secs),
+        );
+        Snapshot {
+            elapsed_secs: time_secs,
+            duration_secs: self.marathon_duration_secs,
+            scoring_rule: rule,
+            count,
+            estimated_pace: self.estimate_pace_at(count, time_secs),
+            time_since_last_hit: self.time_since_last_hit_at(time_secs),
+            longest_drought_secs: self.longest_drought_at(time_secs),
+        }
+    }
+
+    fn longest_drought_at(&self, time_secs: u32) -> Option<u32> {
+        let rule = self.log_entries.scoring_rule;
+        let entries = self
+            .entries()
+            .take_while(|entry| entry.time_secs <= time_secs);
+        droughts(entries, &rule, time_secs)
+            .iter()
+            .map(Drought::secs)
+            .max()
+    }
+
     #[allow(dead_code)]
     pub(crate) fn print_entries(&self) {
         assert_eq!(self.log_entries.scores.len(), self.log_entries.times.len());
         for i in 0..self.log_entries.times.len() {
             println!(
-                "entry: score {} and time {}",
-                self.log_entries.scores[i], self.log_entries.times[i]
+                "entry: {:?} with score {} and time {}",
+                self.log_entries.kinds[i], self.log_entries.scores[i], self.log_entries.times[i]
             );
         }
     }
 
-    pub(crate) fn add_up_5ks(&self) -> u16 {
+    /// Counts the entries from scratch, unlike `count` which is a running count.
+    pub fn add_up_count(&self) -> u32 {
+        self.add_up_tally().count
+    }
+
+    fn add_up_tally(&self) -> Tally {
         assert_eq!(self.log_entries.scores.len(), self.log_entries.times.len());
-        self.log_entries.scores.iter()
-            .map(|score| {
-                if *score == 5000 { 1u16 } else { 0u16 }
-            })
-            .reduce(|acc, x| { acc + x })
-            .unwrap_or(0)
+        let mut tally = Tally::default();
+        for entry in self.entries() {
+            tally.add(&self.log_entries.scoring_rule, &entry);
+        }
+        tally
     }
 
-    pub(crate) fn save_to_file(&self) -> std::io::Result<()> {
-        for i in 0..20 {
-            let num = i.to_string();
-            let path = "data".to_owned() + &num + ".ron";
+    /// Saves the entries to the first of data0.ron, data1.ron, ... that doesn't exist yet, and
+    /// returns the name it was saved to.
+    pub fn save_to_file(&self) -> Result<String, PersistError> {
+        for i in 0..SAVE_FILE_COUNT {
+            let path = save_file_name(i);
             match fs::exists(&path) {
                 Err(err) => {
                     error!("error saving to file: {}", err.to_string());
-                },
+                }
                 Ok(true) => {
                     continue;
-                },
+                }
                 Ok(false) => {
-                    let mut file = File::create_new(path)?;
-                    let serialized = ron::to_string(&self.log_entries).unwrap();
-                    file.write_all(serialized.as_bytes())?;
-
-                    return Ok(());
+                    return self.save_to_path(&path).map(|()| path);
                 }
             }
         }
 
-        Err(io::Error::new(io::ErrorKind::AlreadyExists, "all file names where taken"))
+        Err(PersistError::AllFileNamesTaken {
+            first: save_file_name(0).into(),
+            last: save_file_name(SAVE_FILE_COUNT - 1).into(),
+        })
     }
 
-    pub(crate) fn load_from_file(&mut self, path: &str) -> std::io::Result<()> {
-        if fs::exists(path)? {
-            let contents = fs::read_to_string(path)?;
-            let de_res = ron::from_str::<LogEntries>(&contents);
-            match de_res {
-                Err(err) => {
-                    error!("error reading file: {}", err.to_string());
-                    Err(io::Error::new(io::ErrorKind::InvalidData, "data could not be deserialized"))
-                },
-                Ok(log) => {
-                    self.log_entries = log;
-                    info!("successfully loaded from file!");
-                    Ok(())
-                }
-            }
+    /// Saves the entries to `path`, failing if it already exists.
+    pub fn save_to_path(&self, path: &str) -> Result<(), PersistError> {
+        let mut file = File::create_new(path).map_err(|err| PersistError::io(path, err))?;
+        let serialized = ron::to_string(&self.log_entries).map_err(PersistError::Serialize)?;
+        file.write_all(serialized.as_bytes())
+            .map_err(|err| PersistError::io(path, err))?;
+        Ok(())
+    }
+
+    /// Replaces the entries with the ones saved in `path`, and takes the length of the marathon
+    /// from the file if it has one. The timer isn't changed.
+    pub fn load_from_file(&mut self, path: &str) -> Result<(), PersistError> {
+        if !fs::exists(path).map_err(|err| PersistError::io(path, err))? {
+            return Err(PersistError::NotFound { path: path.into() });
         }
-        else {
-            Err(io::Error::new(io::ErrorKind::NotFound, "file not found"))
+        let contents = fs::read_to_string(path).map_err(|err| PersistError::io(path, err))?;
+        let mut log =
+            ron::from_str::<LogEntries>(&contents).map_err(|err| PersistError::Deserialize {
+                path: path.into(),
+                line: err.position.line,
+                col: err.position.col,
+                source: err.code,
+            })?;
+        log.fill_missing_columns();
+        match log.duration_secs {
+            Some(duration) => self.marathon_duration_secs = duration,
+            None => log.duration_secs = Some(self.marathon_duration_secs),
         }
+        self.log_entries = log;
+        self.active_player = 0;
+        self.tally = self.add_up_tally();
+        info!("successfully loaded from file!");
+        Ok(())
     }
 
     /* pub(crate) fn print_stats(&self) {
@@ -192,3 +1169,494 @@ impl MarathonLog {
         println!();
     } */
 }
+
+/// Estimates the count at the end of the marathon, if it keeps going up at the same rate.
+fn project_count(count: u32, current_time: u32, duration: u32) -> u32 {
+    let pace = f64::from(count) / f64::from(current_time);
+    let remaining_duration = f64::from(duration.saturating_sub(current_time));
+    let remaining_estimate = pace * remaining_duration;
+    let remaining_estimate = remaining_estimate.trunc() as u32;
+    count.saturating_add(remaining_estimate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+
+    fn mock_log(duration: u32) -> (MarathonLog<MockClock>, MockClock) {
+        let clock = MockClock::new();
+        (MarathonLog::with_clock(duration, clock.clone()), clock)
+    }
+
+    #[test]
+    fn pause_resume_test() {
+        let (mut marathon_log, clock) = mock_log(1000);
+        marathon_log.set_pause_policy(PausePolicy::AcceptAtPause);
+        assert!(marathon_log.is_paused());
+        clock.advance_secs(50);
+        assert_eq!(marathon_log.elapsed_secs(), Ok(0));
+
+        marathon_log.resume();
+        clock.advance_secs(100);
+        assert_eq!(marathon_log.elapsed_secs(), Ok(100));
+        marathon_log.pause().unwrap();
+        clock.advance_secs(300);
+        assert_eq!(marathon_log.elapsed_secs(), Ok(100));
+        assert_eq!(
+            marathon_log.try_add_entry(5000),
+            AddEntryResult::TimerPaused
+        );
+
+        marathon_log.resume();
+        clock.advance_secs(20);
+        assert_eq!(marathon_log.try_add_entry(4000), AddEntryResult::Ok);
+        let times: Vec<u32> = marathon_log
+            .entries()
+            .map(|entry| entry.time_secs)
+            .collect();
+        assert_eq!(times, vec![100, 120]);
+        assert_eq!(
+            marathon_log.pauses(),
+            &[Pause {
+                time_secs: 100,
+                real_secs: 300
+            }]
+        );
+    }
+
+    #[test]
+    fn pause_policy_test() {
+        let (mut marathon_log, clock) = mock_log(1000);
+        marathon_log.resume();
+        clock.advance_secs(100);
+        marathon_log.pause().unwrap();
+        clock.advance_secs(60);
+
+        // rejected by default, so nothing is added at a stale time
+        assert_eq!(marathon_log.try_add_entry(5000), AddEntryResult::Paused);
+        assert_eq!(marathon_log.add_no_guess(), AddEntryResult::Paused);
+        assert_eq!(marathon_log.entries().count(), 0);
+
+        marathon_log.set_pause_policy(PausePolicy::Queue);
+        assert_eq!(marathon_log.try_add_entry(5000), AddEntryResult::Queued);
+        assert_eq!(marathon_log.add_skipped(), AddEntryResult::Queued);
+        assert_eq!(marathon_log.queued_entries().len(), 2);
+        assert_eq!(marathon_log.count(), 0);
+        assert_eq!(marathon_log.resume(), 2);
+        assert!(marathon_log.queued_entries().is_empty());
+        assert_eq!(marathon_log.resume(), 0);
+        clock.advance_secs(20);
+        assert_eq!(marathon_log.try_add_entry(5000), AddEntryResult::Ok);
+        let times: Vec<(u32, EntryKind)> = marathon_log
+            .entries()
+            .map(|entry| (entry.time_secs, entry.kind))
+            .collect();
+        assert_eq!(
+            times,
+            vec![
+                (100, EntryKind::Guess),
+                (100, EntryKind::Skipped),
+                (120, EntryKind::Guess)
+            ]
+        );
+        assert_eq!(marathon_log.count(), 2);
+    }
+
+    #[test]
+    fn synced_entry_test() {
+        let synced = |score, time_secs| Entry {
+            score,
+            time_secs,
+            kind: EntryKind::Guess,
+            player: 0,
+            starts_game: false,
+            timing: EntryTiming::OnTime,
+        };
+        let (mut marathon_log, clock) = mock_log(1000);
+        marathon_log.set_long_drought_secs(120);
+        marathon_log.resume();
+        clock.advance_secs(100);
+        marathon_log.pause().unwrap();
+
+        // the same pause policy as entries added here
+        assert_eq!(
+            marathon_log.add_synced_entry(synced(5000, 90)),
+            AddEntryResult::Paused
+        );
+        marathon_log.set_pause_policy(PausePolicy::AcceptAtPause);
+        assert_eq!(
+            marathon_log.add_synced_entry(synced(5000, 150)),
+            AddEntryResult::TimerPaused
+        );
+        marathon_log.set_pause_policy(PausePolicy::Queue);
+        assert_eq!(
+            marathon_log.add_synced_entry(synced(5000, 400)),
+            AddEntryResult::Queued
+        );
+        assert_eq!(marathon_log.resume(), 1);
+
+        // entries keep their own time, and the droughts they end are recorded
+        assert_eq!(
+            marathon_log.add_synced_entry(synced(5000, 250)),
+            AddEntryResult::Ok
+        );
+        let drought = marathon_log.long_droughts()[0];
+        assert_eq!((drought.start_secs, drought.end_secs), (100, 250));
+
+        // entries from after the end go by the grace rule and late entry policy
+        marathon_log.set_grace_period(30, GraceRule::CountNone);
+        marathon_log.set_late_entry_policy(LateEntryPolicy::Reject);
+        assert_eq!(
+            marathon_log.add_synced_entry(synced(5000, 1010)),
+            AddEntryResult::Grace
+        );
+        assert_eq!(
+            marathon_log.add_synced_entry(synced(5000, 1100)),
+            AddEntryResult::MarathonOver
+        );
+
+        let times: Vec<u32> = marathon_log
+            .entries()
+            .map(|entry| entry.time_secs)
+            .collect();
+        assert_eq!(times, vec![100, 100, 250, 1010]);
+        assert_eq!(marathon_log.count(), 3);
+    }
+
+    #[test]
+    fn pace_test() {
+        let (mut marathon_log, clock) = mock_log(1000);
+        marathon_log.resume();
+        clock.advance_secs(100);
+        assert_eq!(marathon_log.estimate_pace(), None);
+
+        marathon_log.try_add_entry(5000);
+        marathon_log.try_add_entry(2500);
+        marathon_log.try_add_entry(5000);
+        clock.advance_secs(100);
+        // 2 5ks in 200 seconds, so 8 more in the remaining 800 seconds
+        assert_eq!(marathon_log.estimate_pace(), Some(10));
+
+        clock.advance_secs(1000);
+        assert_eq!(marathon_log.estimate_pace(), Some(2));
+    }
+
+    #[test]
+    fn last_5k_test() {
+        let (mut marathon_log, clock) = mock_log(1000);
+        marathon_log.set_elapsed_secs(400);
+        marathon_log.resume();
+        assert_eq!(marathon_log.time_since_last_hit(), None);
+
+        clock.advance_secs(10);
+        marathon_log.try_add_entry(5000);
+        clock.advance_secs(30);
+        marathon_log.try_add_miss(4999);
+        marathon_log.add_no_guess();
+        clock.advance_secs(15);
+        assert_eq!(marathon_log.time_since_last_hit(), Some(45));
+
+        marathon_log.pause().unwrap();
+        clock.advance_secs(600);
+        assert_eq!(marathon_log.time_since_last_hit(), Some(45));
+        marathon_log.resume();
+        clock.advance_secs(5);
+        marathon_log.try_add_entry(5000);
+        assert_eq!(marathon_log.time_since_last_hit(), Some(0));
+        assert_eq!(marathon_log.count(), 2);
+    }
+
+    #[test]
+    fn end_of_marathon_test() {
+        let (mut marathon_log, clock) = mock_log(100);
+        marathon_log.resume();
+        clock.advance_secs(90);
+        assert!(!marathon_log.is_over());
+        assert_eq!(marathon_log.try_add_entry(5000), AddEntryResult::Ok);
+        clock.advance_secs(20);
+        assert!(marathon_log.is_over());
+        assert_eq!(marathon_log.elapsed_secs(), Ok(100));
+
+        marathon_log.end();
+        assert!(!marathon_log.is_paused());
+        assert!(marathon_log.is_over());
+        assert!(marathon_log.pauses().is_empty());
+        assert_eq!(marathon_log.try_add_entry(5000), AddEntryResult::Late);
+        let last_entry = marathon_log.last_entry().unwrap();
+        assert_eq!(last_entry.timing, EntryTiming::Late);
+        assert_eq!(last_entry.time_secs, 100);
+        assert_eq!(marathon_log.count(), 1);
+        assert_eq!(marathon_log.add_up_count(), 1);
+
+        marathon_log.set_late_entry_policy(LateEntryPolicy::Reject);
+        assert_eq!(
+            marathon_log.try_add_entry(5000),
+            AddEntryResult::MarathonOver
+        );
+        assert_eq!(marathon_log.entries().count(), 2);
+        marathon_log.set_late_entry_policy(LateEntryPolicy::Accept);
+        assert_eq!(marathon_log.try_add_entry(5000), AddEntryResult::Ok);
+        assert_eq!(marathon_log.count(), 2);
+    }
+
+    #[test]
+    fn grace_period_test() {
+        let (mut marathon_log, clock) = mock_log(100);
+        marathon_log.set_grace_period(30, GraceRule::CountFirst);
+        marathon_log.resume();
+        clock.advance_secs(110);
+        assert!(marathon_log.is_over());
+        assert_eq!(marathon_log.grace_secs_left(), Some(20));
+        assert_eq!(marathon_log.try_add_entry(5000), AddEntryResult::Grace);
+        assert_eq!(marathon_log.try_add_entry(5000), AddEntryResult::Grace);
+        let timings: Vec<EntryTiming> = marathon_log.entries().map(|entry| entry.timing).collect();
+        assert_eq!(
+            timings,
+            [
+                EntryTiming::Grace { counted: true },
+                EntryTiming::Grace { counted: false }
+            ]
+        );
+        assert_eq!(marathon_log.count(), 1);
+        assert_eq!(marathon_log.last_entry().unwrap().time_secs, 100);
+
+        clock.advance_secs(20);
+        assert!(!marathon_log.in_grace_period());
+        assert_eq!(marathon_log.try_add_entry(5000), AddEntryResult::Late);
+
+        let (mut ended_log, clock) = mock_log(100);
+        ended_log.set_grace_period(30, GraceRule::CountAll);
+        ended_log.resume();
+        clock.advance_secs(100);
+        assert!(ended_log.in_grace_period());
+        ended_log.end();
+        assert!(!ended_log.in_grace_period());
+    }
+
+    #[test]
+    fn long_droughts_test() {
+        let (mut marathon_log, clock) = mock_log(1000);
+        marathon_log.set_long_drought_secs(100);
+        marathon_log.resume();
+        clock.advance_secs(50);
+        marathon_log.try_add_entry(5000);
+        clock.advance_secs(150);
+        marathon_log.try_add_entry(2000);
+        clock.advance_secs(10);
+        marathon_log.try_add_entry(5000);
+        clock.advance_secs(90);
+        marathon_log.try_add_entry(5000);
+        clock.advance_secs(700);
+        marathon_log.end();
+        let spans: Vec<(u32, u32, u32)> = marathon_log
+            .long_droughts()
+            .iter()
+            .map(|d| (d.start_secs, d.end_secs, d.rounds))
+            .collect();
+        assert_eq!(spans, [(50, 210, 1), (300, 1000, 0)]);
+        assert_eq!(
+            marathon_log.snapshot_at(250).longest_drought_secs,
+            Some(160)
+        );
+    }
+
+    #[test]
+    fn headstart_test() {
+        let (mut marathon_log, clock) = mock_log(1000);
+        marathon_log.resume();
+        clock.advance_secs(5);
+        marathon_log.try_add_entry(5000);
+        assert_eq!(marathon_log.add_headstart(3), 2);
+        assert_eq!(marathon_log.add_headstart(1), 0);
+        assert_eq!(marathon_log.count(), 3);
+
+        // adding would be rejected in a zero-length log or a paused one, but a headstart isn't
+        let (mut marathon_log, _) = mock_log(0);
+        marathon_log.resume();
+        assert!(marathon_log.is_over());
+        assert_eq!(marathon_log.add_headstart(2), 2);
+        assert_eq!(marathon_log.count(), 2);
+        let (mut marathon_log, _) = mock_log(1000);
+        marathon_log.set_pause_policy(PausePolicy::Reject);
+        assert_eq!(marathon_log.add_headstart(1), 1);
+        assert_eq!(marathon_log.count(), 1);
+    }
+
+    #[test]
+    fn scoring_rule_test() {
+        let (mut marathon_log, clock) = mock_log(1000);
+        marathon_log.resume();
+        clock.advance_secs(100);
+        for score in [4950, 5000, 4000, 4900] {
+            marathon_log.try_add_entry(score);
+        }
+        marathon_log.set_scoring_rule(ScoringRule::Threshold { min_score: 4900 });
+        assert_eq!(marathon_log.count(), 3);
+        assert_eq!(marathon_log.estimate_pace(), Some(30));
+
+        marathon_log.set_scoring_rule(ScoringRule::Streak { min_score: 4900 });
+        assert_eq!(marathon_log.count(), 2);
+        assert_eq!(marathon_log.estimate_pace(), None);
+        clock.advance_secs(10);
+        assert_eq!(marathon_log.time_since_last_hit(), Some(10));
+
+        marathon_log.set_scoring_rule(ScoringRule::TotalPoints);
+        assert_eq!(marathon_log.count(), 18_850);
+        assert_eq!(marathon_log.add_headstart(30_000), 3);
+        assert_eq!(marathon_log.count(), 30_000);
+        assert_eq!(marathon_log.snapshot_at(110).count, 30_000);
+    }
+
+    #[test]
+    fn new_game_test() {
+        let (mut marathon_log, _) = mock_log(1000);
+        marathon_log.resume();
+        assert_eq!(marathon_log.current_game(), None);
+        marathon_log.try_add_entry(5000);
+        marathon_log.try_add_entry(4000);
+        assert_eq!(marathon_log.current_game().unwrap().total_score(), 9000);
+        marathon_log.start_new_game();
+        assert_eq!(marathon_log.current_game(), None);
+        marathon_log.try_add_entry(5000);
+        assert_eq!(marathon_log.current_game().unwrap().rounds.len(), 1);
+        assert_eq!(marathon_log.game_stats().games, 2);
+        assert!(marathon_log.last_entry().unwrap().starts_game);
+    }
+
+    #[test]
+    fn load_errors_test() {
+        let mut marathon_log = MarathonLog::new(60);
+        let missing = std::env::temp_dir().join("geoguessr_tracker_missing_log.ron");
+        let res = marathon_log.load_from_file(missing.to_str().unwrap());
+        assert!(matches!(res, Err(PersistError::NotFound { .. })));
+
+        let invalid = std::env::temp_dir().join("geoguessr_tracker_invalid_log.ron");
+        fs::write(&invalid, "(scores:[5000],\ntimes:[oops])").unwrap();
+        let res = marathon_log.load_from_file(invalid.to_str().unwrap());
+        fs::remove_file(&invalid).unwrap();
+        match res {
+            Err(PersistError::Deserialize { line, .. }) => assert_eq!(line, 2),
+            other => panic!("expected a deserialize error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn load_old_format_test() {
+        let mut marathon_log = MarathonLog::new(60);
+        let old = std::env::temp_dir().join("geoguessr_tracker_old_log.ron");
+        fs::write(&old, "(scores:[5000,1234],times:[10,20])").unwrap();
+        let res = marathon_log.load_from_file(old.to_str().unwrap());
+        fs::remove_file(&old).unwrap();
+        assert!(res.is_ok());
+        assert_eq!(marathon_log.log_entries.kinds, vec![EntryKind::Guess; 2]);
+        assert_eq!(marathon_log.log_entries.players, vec![0; 2]);
+        assert_eq!(marathon_log.log_entries.game_starts, vec![false; 2]);
+        assert_eq!(
+            marathon_log.log_entries.timings,
+            vec![EntryTiming::OnTime; 2]
+        );
+        assert_eq!(marathon_log.player_names().len(), 1);
+        // the length wasn't saved, so the one the log was made with is kept
+        assert_eq!(marathon_log.duration_secs(), 60);
+
+        let saved = std::env::temp_dir().join("geoguessr_tracker_duration_log.ron");
+        let saved = saved.to_str().unwrap();
+        let _ = fs::remove_file(saved);
+        MarathonLog::new(4 * 60 * 60).save_to_path(saved).unwrap();
+        let res = MarathonLog::from_file(60, saved);
+        fs::remove_file(saved).unwrap();
+        assert_eq!(res.unwrap().duration_secs(), 4 * 60 * 60);
+    }
+
+    #[test]
+    fn save_queued_test() {
+        let (mut marathon_log, clock) = mock_log(1000);
+        marathon_log.set_pause_policy(PausePolicy::Queue);
+        marathon_log.resume();
+        clock.advance_secs(100);
+        marathon_log.try_add_entry(5000);
+        marathon_log.pause().unwrap();
+        assert_eq!(marathon_log.try_add_entry(5000), AddEntryResult::Queued);
+        assert_eq!(marathon_log.add_no_guess(), AddEntryResult::Queued);
+
+        // quitting while paused keeps the queued entries in the file
+        let saved = std::env::temp_dir().join("geoguessr_tracker_queued_log.ron");
+        let saved = saved.to_str().unwrap();
+        let _ = fs::remove_file(saved);
+        marathon_log.save_to_path(saved).unwrap();
+        let res = MarathonLog::from_file(1000, saved);
+        fs::remove_file(saved).unwrap();
+        let mut loaded = res.unwrap();
+        assert_eq!(loaded.queued_entries(), marathon_log.queued_entries());
+        assert_eq!(loaded.entries().count(), 1);
+
+        loaded.set_elapsed_secs(100);
+        assert_eq!(loaded.resume(), 2);
+        let kinds: Vec<(u32, EntryKind)> = loaded
+            .entries()
+            .map(|entry| (entry.time_secs, entry.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (100, EntryKind::Guess),
+                (100, EntryKind::Guess),
+                (100, EntryKind::NoGuess)
+            ]
+        );
+        assert_eq!(loaded.count(), 2);
+    }
+
+    #[test]
+    fn relay_test() {
+        let (mut marathon_log, clock) = mock_log(1000);
+        marathon_log.set_player_names(vec!["Ana".to_owned(), "".to_owned()]);
+        marathon_log.resume();
+        clock.advance_secs(100);
+        marathon_log.try_add_entry(5000);
+        marathon_log.try_add_entry(5000);
+        marathon_log.set_active_player(1);
+        clock.advance_secs(200);
+        marathon_log.try_add_entry(5000);
+        marathon_log.try_add_miss(10);
+        marathon_log.set_active_player(5);
+        assert_eq!(marathon_log.active_player(), 1);
+        assert_eq!(
+            marathon_log.handoffs(),
+            &[Handoff {
+                time_secs: 100,
+                from: 0,
+                to: 1
+            }]
+        );
+
+        let stats = marathon_log.player_stats();
+        assert_eq!(stats[0].name, "Ana");
+        assert_eq!(stats[1].name, "Player 2");
+        assert_eq!(
+            (stats[0].count, stats[0].rounds, stats[0].active_secs),
+            (2, 2, 100)
+        );
+        assert_eq!(
+            (stats[1].count, stats[1].rounds, stats[1].active_secs),
+            (1, 2, 200)
+        );
+        assert_eq!(stats[0].per_hour, Some(72.0));
+        assert_eq!(marathon_log.count(), 3);
+
+        // player indexes are a u8, so adding one past the limit fails instead of wrapping
+        assert_eq!(marathon_log.find_or_add_player("Ana").unwrap(), 0);
+        for i in 2..MAX_PLAYERS {
+            assert_eq!(
+                marathon_log.find_or_add_player(&format!("P{i}")).unwrap(),
+                i as u8
+            );
+        }
+        assert!(matches!(
+            marathon_log.find_or_add_player("One too many"),
+            Err(PersistError::TooManyPlayers { .. })
+        ));
+        assert_eq!(marathon_log.player_names().len(), MAX_PLAYERS);
+    }
+}
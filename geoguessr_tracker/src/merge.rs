@@ -0,0 +1,349 @@
+//! Combining several saved logs of one marathon into a single log, and splitting a log in two.
+//!
+//! When the tracker crashes or is restarted mid-marathon, the run ends up spread over several
+//! files. The later files can contain entries from the earlier ones (if they were loaded before
+//! continuing), or can start from the wrong time (if the headstart wasn't set when restarting).
+
+use std::collections::HashMap;
+
+use crate::analysis::Drought;
+use crate::error::PersistError;
+use crate::i18n::{fmt_int, trf};
+use crate::marathon_log::{Entry, EntryKind, Handoff, MarathonLog, Pause};
+use crate::utils::time_counter;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeOptions {
+    /// Shift each log that starts before the end of the previous one, so that it starts where
+    /// the previous one ended instead.
+    pub rebase: bool,
+}
+
+/// Two logs with entries in the same stretch of time, which usually means one of them has the
+/// wrong times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overlap {
+    /// The indices of the two logs in the list that was merged.
+    pub first: usize,
+    pub second: usize,
+    pub start_secs: u32,
+    pub end_secs: u32,
+}
+
+pub struct MergeReport {
+    pub merged: MarathonLog,
+    /// How many entries were left out because an earlier log had the same entry.
+    pub duplicates: usize,
+    /// How far each log was shifted by rebasing, in the same order as the logs.
+    pub shifts_secs: Vec<u32>,
+    /// Overlaps between logs, after removing duplicates and rebasing.
+    pub overlaps: Vec<Overlap>,
+}
+
+impl MergeReport {
+    /// A short description of what was merged, for showing after a merge.
+    pub fn summary(&self) -> String {
+        let mut summary = trf(
+            "merged {0} entries, {1} duplicates left out",
+            &[
+                &fmt_int(self.merged.entries().count() as u64),
+                &fmt_int(self.duplicates as u64),
+            ],
+        );
+        for (i, shift) in self.shifts_secs.iter().enumerate() {
+            if *shift > 0 {
+                summary += "\n";
+                summary += &trf(
+                    "log {0} moved {1} later",
+                    &[&fmt_int(i as u64 + 1), &time_counter(*shift)],
+                );
+            }
+        }
+        for overlap in &self.overlaps {
+            summary += "\n";
+            summary += &trf(
+                "logs {0} and {1} overlap from {2} to {3}",
+                &[
+                    &fmt_int(overlap.first as u64 + 1),
+                    &fmt_int(overlap.second as u64 + 1),
+                    &time_counter(overlap.start_secs),
+                    &time_counter(overlap.end_secs),
+                ],
+            );
+        }
+        summary
+    }
+}
+
+/// Loads the logs at `paths` and merges them, see [`merge_logs`]. Files that don't have the
+/// length of the marathon saved are taken to last `duration_secs`.
+pub fn merge_files(
+    paths: &[&str],
+    options: MergeOptions,
+    duration_secs: u32,
+) -> Result<MergeReport, PersistError> {
+    let logs = paths
+        .iter()
+        .map(|path| MarathonLog::from_file(duration_secs, path))
+        .collect::<Result<Vec<_>, _>>()?;
+    merge_logs(&logs, options)
+}
+
+/// Merges `logs`, which should be in the order they were recorded, into one log in time order.
+///
+/// An entry is a duplicate if an earlier log has an entry with the same score, time, kind and
+/// player name that wasn't already matched with another duplicate. Fails if the logs have more
+/// players between them than a log can have.
+pub fn merge_logs(
+    logs: &[MarathonLog],
+    options: MergeOptions,
+) -> Result<MergeReport, PersistError> {
+    let duration = logs
+        .iter()
+        .map(|log| log.duration_secs())
+        .max()
+        .unwrap_or(0);
+    let mut merged = MarathonLog::new(duration);
+    let mut seen: HashMap<(u16, u32, EntryKind, String), usize> = HashMap::new();
+    let mut duplicates = 0;
+    let mut shifts_secs = Vec::new();
+    let mut ranges: Vec<Option<(u32, u32)>> = Vec::new();
+    let mut previous_end = None;
+    // keep the players, details and scoring rule of the first log
+    if let Some(first) = logs.first() {
+        merged.set_player_names(first.player_names().to_vec());
+        *merged.metadata_mut() = first.metadata().clone();
+        merged.set_scoring_rule(first.scoring_rule());
+    }
+
+    for marathon_log in logs {
+        let mut unique = Vec::new();
+        let mut log_seen = Vec::new();
+        for entry in marathon_log.entries() {
+            let name = marathon_log.player_name(entry.player);
+            let key = (entry.score, entry.time_secs, entry.kind, name);
+            match seen.get_mut(&key) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    duplicates += 1;
+                }
+                _ => {
+                    unique.push(entry);
+                    log_seen.push(key);
+                }
+            }
+        }
+        for key in log_seen {
+            *seen.entry(key).or_default() += 1;
+        }
+
+        let start = unique.first().map(|entry| entry.time_secs);
+        let shift = match (options.rebase, start, previous_end) {
+            (true, Some(start), Some(end)) if start < end => end - start,
+            _ => 0,
+        };
+        shifts_secs.push(shift);
+        let range = start.map(|start| {
+            let end = unique.last().map_or(start, |entry| entry.time_secs);
+            (start + shift, end + shift)
+        });
+        if let Some((_, end)) = range {
+            previous_end = Some(previous_end.map_or(end, |previous: u32| previous.max(end)));
+        }
+        ranges.push(range);
+
+        let players = player_mapping(marathon_log, &mut merged)?;
+        for entry in unique {
+            merged.insert_entry(Entry {
+                time_secs: entry.time_secs + shift,
+                player: players[usize::from(entry.player)],
+                ..entry
+            });
+        }
+        for handoff in marathon_log.handoffs() {
+            merged.insert_handoff(Handoff {
+                time_secs: handoff.time_secs + shift,
+                from: players[usize::from(handoff.from)],
+                to: players[usize::from(handoff.to)],
+            });
+        }
+        for pause in marathon_log.pauses() {
+            merged.insert_pause(Pause {
+                time_secs: pause.time_secs + shift,
+                ..*pause
+            });
+        }
+        for drought in marathon_log.long_droughts() {
+            merged.insert_long_drought(Drought {
+                start_secs: drought.start_secs + shift,
+                end_secs: drought.end_secs + shift,
+                ..*drought
+            });
+        }
+    }
+
+    Ok(MergeReport {
+        merged,
+        duplicates,
+        shifts_secs,
+        overlaps: find_overlaps(&ranges),
+    })
+}
+
+/// Splits `marathon_log` into the entries before `at_secs`, and the entries from then on. Times
+/// aren't changed, so both parts still line up with the marathon clock.
+pub fn split_log(marathon_log: &MarathonLog, at_secs: u32) -> (MarathonLog, MarathonLog) {
+    let duration = marathon_log.duration_secs();
+    let mut before = MarathonLog::new(duration);
+    let mut after = MarathonLog::new(duration);
+    for part in [&mut before, &mut after] {
+        part.set_player_names(marathon_log.player_names().to_vec());
+        *part.metadata_mut() = marathon_log.metadata().clone();
+        part.set_scoring_rule(marathon_log.scoring_rule());
+    }
+
+    for entry in marathon_log.entries() {
+        match entry.time_secs < at_secs {
+            true => before.insert_entry(entry),
+            false => after.insert_entry(entry),
+        }
+    }
+    for &handoff in marathon_log.handoffs() {
+        match handoff.time_secs < at_secs {
+            true => before.insert_handoff(handoff),
+            false => after.insert_handoff(handoff),
+        }
+    }
+    for &pause in marathon_log.pauses() {
+        match pause.time_secs < at_secs {
+            true => before.insert_pause(pause),
+            false => after.insert_pause(pause),
+        }
+    }
+    for &drought in marathon_log.long_droughts() {
+        match drought.start_secs < at_secs {
+            true => before.insert_long_drought(drought),
+            false => after.insert_long_drought(drought),
+        }
+    }
+
+    (before, after)
+}
+
+/// Maps each player index in `marathon_log` to the index of the player with the same name in
+/// `merged`, adding players to `merged` as needed.
+fn player_mapping(
+    marathon_log: &MarathonLog,
+    merged: &mut MarathonLog,
+) -> Result<Vec<u8>, PersistError> {
+    marathon_log
+        .player_names()
+        .iter()
+        .map(|name| merged.find_or_add_player(name))
+        .collect()
+}
+
+fn find_overlaps(ranges: &[Option<(u32, u32)>]) -> Vec<Overlap> {
+    let mut overlaps = Vec::new();
+    for (first, first_range) in ranges.iter().enumerate() {
+        for (second, second_range) in ranges.iter().enumerate().skip(first + 1) {
+            if let (Some((start_a, end_a)), Some((start_b, end_b))) = (first_range, second_range) {
+                let start_secs = *start_a.max(start_b);
+                let end_secs = *end_a.min(end_b);
+                if start_secs < end_secs {
+                    overlaps.push(Overlap {
+                        first,
+                        second,
+                        start_secs,
+                        end_secs,
+                    });
+                }
+            }
+        }
+    }
+    overlaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marathon_log::EntryTiming;
+    use crate::scoring::ScoringRule;
+
+    fn log_with(entries: &[(u16, u32)]) -> MarathonLog {
+        let entries = entries.iter().map(|&(score, time_secs)| Entry {
+            score,
+            time_secs,
+            kind: EntryKind::Guess,
+            player: 0,
+            starts_game: false,
+            timing: EntryTiming::OnTime,
+        });
+        MarathonLog::from_entries(1000, entries)
+    }
+
+    fn times(marathon_log: &MarathonLog) -> Vec<u32> {
+        marathon_log
+            .entries()
+            .map(|entry| entry.time_secs)
+            .collect()
+    }
+
+    #[test]
+    fn merge_duplicates_test() {
+        // the second file was saved after loading the first one and playing on
+        let first = log_with(&[(5000, 10), (5000, 10), (300, 20)]);
+        let second = log_with(&[(5000, 10), (5000, 10), (300, 20), (5000, 50)]);
+        let report = merge_logs(&[first, second], MergeOptions::default()).unwrap();
+        assert_eq!(report.duplicates, 3);
+        assert_eq!(times(&report.merged), vec![10, 10, 20, 50]);
+        assert_eq!(report.merged.count(), 3);
+        assert!(report.overlaps.is_empty());
+    }
+
+    #[test]
+    fn merge_rebase_test() {
+        // the tracker was restarted without setting the headstart time
+        let first = log_with(&[(5000, 100), (4000, 200)]);
+        let second = log_with(&[(5000, 5), (5000, 50)]);
+
+        let report = merge_logs(&[first, second], MergeOptions::default()).unwrap();
+        assert_eq!(times(&report.merged), vec![5, 50, 100, 200]);
+        assert!(report.overlaps.is_empty());
+
+        let first = log_with(&[(5000, 100), (4000, 200)]);
+        let second = log_with(&[(5000, 150), (5000, 300)]);
+        let report = merge_logs(&[first, second], MergeOptions::default()).unwrap();
+        assert_eq!(report.overlaps.len(), 1);
+        assert_eq!(
+            (report.overlaps[0].start_secs, report.overlaps[0].end_secs),
+            (150, 200)
+        );
+
+        let first = log_with(&[(5000, 100), (4000, 200)]);
+        let second = log_with(&[(5000, 150), (5000, 300)]);
+        let report = merge_logs(&[first, second], MergeOptions { rebase: true }).unwrap();
+        assert_eq!(report.shifts_secs, vec![0, 50]);
+        assert_eq!(times(&report.merged), vec![100, 200, 200, 350]);
+        assert!(report.overlaps.is_empty());
+    }
+
+    #[test]
+    fn split_test() {
+        let mut marathon_log = log_with(&[(5000, 10), (1000, 20), (5000, 30)]);
+        marathon_log.set_player_names(vec!["Ana".to_owned(), "Bo".to_owned()]);
+        marathon_log.set_scoring_rule(ScoringRule::TotalPoints);
+        let (before, after) = split_log(&marathon_log, 20);
+        assert_eq!(times(&before), vec![10]);
+        assert_eq!(times(&after), vec![20, 30]);
+        assert_eq!(after.count(), 6000);
+        assert_eq!(after.player_names(), &["Ana", "Bo"]);
+        assert_eq!(before.scoring_rule(), ScoringRule::TotalPoints);
+        assert_eq!(after.scoring_rule(), ScoringRule::TotalPoints);
+
+        let report = merge_logs(&[before, after], MergeOptions::default()).unwrap();
+        assert!(report.merged.entries().eq(marathon_log.entries()));
+        assert_eq!(report.merged.scoring_rule(), ScoringRule::TotalPoints);
+        assert_eq!(report.merged.count(), 11_000);
+    }
+}
@@ -0,0 +1,144 @@
+//! Details about a run that are saved along with its entries, so that saved logs can be told apart.
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameMode {
+    #[default]
+    Moving,
+    NoMove,
+    /// No moving, panning or zooming.
+    Nmpz,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 3] = [GameMode::Moving, GameMode::NoMove, GameMode::Nmpz];
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GameMode::Moving => "Moving",
+            GameMode::NoMove => "NM",
+            GameMode::Nmpz => "NMPZ",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Who ran a marathon, where and how. Every field can be left empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)] // so that fields can be added without breaking saved files
+pub struct RunMetadata {
+    /// Who ran the marathon, or the team name for a log shared by several players.
+    pub player: String,
+    pub map_name: String,
+    /// The ID from the map's URL, for maps with common names.
+    pub map_id: String,
+    pub game_mode: GameMode,
+    /// The count aimed for by the end of the marathon, e.g. a number of 5ks.
+    pub target: Option<u32>,
+    /// The rule set, e.g. the round time limit or what counts as a skip.
+    pub rules: String,
+    /// When the timer was first started, in seconds since the Unix epoch.
+    pub started_at_unix_secs: Option<u64>,
+    /// The hardware and setup used, e.g. the browser, monitor and input method.
+    pub setup: String,
+    pub notes: String,
+}
+
+impl RunMetadata {
+    /// Records the current time as the start of the run, unless a start time is already set.
+    pub fn set_started_now(&mut self) {
+        if self.started_at_unix_secs.is_none() {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH);
+            self.started_at_unix_secs = now.ok().map(|since_epoch| since_epoch.as_secs());
+        }
+    }
+
+    /// The start time as a UTC date and time, or an empty string if it isn't set.
+    pub fn started_at_txt(&self) -> String {
+        self.started_at_unix_secs
+            .map_or(String::new(), format_unix_time)
+    }
+
+    /// Whether any of the text fields or the game mode contain `query`, ignoring case. An empty
+    /// query matches every run.
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return true;
+        }
+        let game_mode = self.game_mode.to_string();
+        [
+            &self.player,
+            &self.map_name,
+            &self.map_id,
+            &game_mode,
+            &self.rules,
+            &self.setup,
+            &self.notes,
+        ]
+        .iter()
+        .any(|field| field.to_lowercase().contains(&query))
+    }
+}
+
+/// Formats seconds since the Unix epoch as `YYYY-MM-DD HH:MM UTC`.
+pub fn format_unix_time(unix_secs: u64) -> String {
+    let days = (unix_secs / 86400) as i64;
+    let secs_of_day = unix_secs % 86400;
+    // days to a civil date, from Howard Hinnant's `civil_from_days`
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_test() {
+        assert_eq!(format_unix_time(0), "1970-01-01 00:00 UTC");
+        assert_eq!(format_unix_time(951_827_696), "2000-02-29 12:34 UTC");
+        assert_eq!(format_unix_time(1_735_689_599), "2024-12-31 23:59 UTC");
+
+        let metadata = RunMetadata {
+            player: "Ana".to_owned(),
+            map_name: "A Diverse World".to_owned(),
+            game_mode: GameMode::Nmpz,
+            ..Default::default()
+        };
+        assert!(metadata.matches(""));
+        assert!(metadata.matches("diverse"));
+        assert!(metadata.matches("nmpz"));
+        assert!(!metadata.matches("moving"));
+
+        let mut started = metadata.clone();
+        started.set_started_now();
+        let started_at = started.started_at_unix_secs;
+        assert!(started_at.is_some());
+        started.set_started_now();
+        assert_eq!(started.started_at_unix_secs, started_at);
+    }
+}
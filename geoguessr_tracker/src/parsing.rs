@@ -0,0 +1,475 @@
+//! Parsing of text copied from the GeoGuessr round result screen.
+//!
+//! The text can be just the score (e.g. "4,987") or a larger chunk of the result screen, like
+//! "Round 3/5", "4 987 points", "12.3 km" and "0:42". Thousand separators can be commas, periods
+//! or spaces, so scores copied from different locales all parse.
+
+use std::fmt;
+
+use log::debug;
+
+use crate::i18n::{tr, trf};
+
+/// Everything that could be found in the text from a round result screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoundResult {
+    pub score: u16,
+    pub distance: Option<Distance>,
+    pub time_taken_secs: Option<u32>,
+    pub round: Option<RoundIndex>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Distance {
+    pub value: f64,
+    pub unit: DistanceUnit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistanceUnit {
+    Metres,
+    Kilometres,
+    Feet,
+    Yards,
+    Miles,
+}
+
+/// The round number, and the number of rounds in the game if it was shown (e.g. "Round 3/5").
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundIndex {
+    pub number: u8,
+    pub out_of: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input had no text in it.
+    Empty,
+    /// The input had text, but nothing in it looked like a score.
+    MissingScore,
+    /// A number was found, but its separators didn't make sense. Contains the number as written.
+    InvalidNumber(String),
+    /// The score is higher than 5000.
+    ScoreOutOfRange(u32),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            ParseError::Empty => tr("no text in input"),
+            ParseError::MissingScore => tr("no score found in input"),
+            ParseError::InvalidNumber(raw) => trf("\"{0}\" is not a valid number", &[raw]),
+            ParseError::ScoreOutOfRange(score) => trf("{0} is higher than 5000", &[score]),
+        };
+        f.write_str(&message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Distance {
+    pub fn in_metres(&self) -> f64 {
+        let metres_per_unit = match self.unit {
+            DistanceUnit::Metres => 1.0,
+            DistanceUnit::Kilometres => 1000.0,
+            DistanceUnit::Feet => 0.3048,
+            DistanceUnit::Yards => 0.9144,
+            DistanceUnit::Miles => 1609.344,
+        };
+        self.value * metres_per_unit
+    }
+}
+
+/// What the AutoHotkey script pastes when a new game starts, instead of a round result.
+pub const NEW_GAME_COMMAND: &str = "new game";
+
+/// Whether pasted text is the new game command rather than a round result.
+pub fn is_new_game_command(input_str: &str) -> bool {
+    input_str.trim().eq_ignore_ascii_case(NEW_GAME_COMMAND)
+}
+
+/// Gets only the score from the text of a round result screen.
+pub fn score_from_str(input_str: &str) -> Result<u16, ParseError> {
+    parse_round_result(input_str).map(|res| res.score)
+}
+
+/// Gets the score, and anything else that can be found, from the text of a round result screen.
+///
+/// A number followed by a word like "points" is preferred as the score, otherwise the first
+/// number that isn't a distance, time or round number is used.
+pub fn parse_round_result(input_str: &str) -> Result<RoundResult, ParseError> {
+    if input_str.trim().is_empty() {
+        return Err(ParseError::Empty);
+    }
+    let tokens = tokenize(input_str);
+    debug!("score tokens: {:?}", &tokens);
+
+    let mut labelled_score = None;
+    let mut bare_score = None;
+    let mut distance = None;
+    // summed in u64 so that adding up times can't overflow, see the conversion below
+    let mut time_taken_secs: Option<u64> = None;
+    let mut round = None;
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let next = tokens.get(i + 1);
+        match &tokens[i] {
+            Token::Time(secs) => {
+                time_taken_secs = Some(
+                    time_taken_secs
+                        .unwrap_or(0)
+                        .saturating_add(u64::from(*secs)),
+                );
+            }
+            Token::Word(word) if is_round_word(word) => {
+                if let Some(Token::Number(raw)) = next {
+                    let number = parse_number(raw)?;
+                    let out_of = match (tokens.get(i + 2), tokens.get(i + 3)) {
+                        (Some(Token::Slash), Some(Token::Number(out_of_raw))) => {
+                            i += 2;
+                            Some(small_int(parse_number(out_of_raw)?, out_of_raw)?)
+                        }
+                        _ => None,
+                    };
+                    round = Some(RoundIndex {
+                        number: small_int(number, raw)?,
+                        out_of,
+                    });
+                    i += 1;
+                }
+            }
+            Token::Number(raw) => {
+                let value = parse_number(raw)?;
+                match next {
+                    Some(Token::Slash) => {
+                        if let Some(Token::Number(out_of_raw)) = tokens.get(i + 2) {
+                            let out_of = parse_number(out_of_raw)?;
+                            round = Some(RoundIndex {
+                                number: small_int(value, raw)?,
+                                out_of: Some(small_int(out_of, out_of_raw)?),
+                            });
+                            i += 1;
+                        }
+                    }
+                    Some(Token::Word(word)) => {
+                        if let Some(unit) = distance_unit(word) {
+                            distance = Some(Distance { value, unit });
+                        } else if let Some(secs_per_unit) = time_unit(word) {
+                            let secs = (value * f64::from(secs_per_unit)).round() as u64;
+                            time_taken_secs =
+                                Some(time_taken_secs.unwrap_or(0).saturating_add(secs));
+                        } else if is_points_word(word) {
+                            labelled_score = labelled_score.or(Some((value, raw)));
+                        } else {
+                            bare_score = bare_score.or(Some((value, raw)));
+                        }
+                        i += 1;
+                    }
+                    _ => {
+                        bare_score = bare_score.or(Some((value, raw)));
+                    }
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    let (score, raw) = labelled_score
+        .or(bare_score)
+        .ok_or(ParseError::MissingScore)?;
+    if score.fract() != 0.0 {
+        return Err(ParseError::InvalidNumber(raw.clone()));
+    }
+    if score > 5000.0 {
+        return Err(ParseError::ScoreOutOfRange(
+            score.min(f64::from(u32::MAX)) as u32
+        ));
+    }
+
+    Ok(RoundResult {
+        score: score as u16,
+        distance,
+        // a time too long to count in seconds is taken as not readable
+        time_taken_secs: time_taken_secs.and_then(|secs| u32::try_from(secs).ok()),
+        round,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A number as written, including any separators.
+    Number(String),
+    /// A lowercase word.
+    Word(String),
+    /// A time written like "1:05" or "1:02:03", in seconds.
+    Time(u32),
+    Slash,
+}
+
+fn tokenize(input_str: &str) -> Vec<Token> {
+    let chars: Vec<char> = input_str.chars().collect();
+    let mut tokens = Vec::new();
+
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+        if ch.is_ascii_digit() {
+            let start = i;
+            i = scan_number(&chars, i);
+            if chars.get(i) == Some(&':') && chars.get(i + 1).is_some_and(char::is_ascii_digit) {
+                let end = scan_time(&chars, i);
+                let time_str: String = chars[start..end].iter().collect();
+                if let Some(secs) = time_from_str(&time_str) {
+                    tokens.push(Token::Time(secs));
+                    i = end;
+                    continue;
+                }
+                // a time that can't be read is skipped past, so that its last parts aren't read
+                // as a shorter time
+                tokens.push(Token::Number(chars[start..i].iter().collect()));
+                i = end;
+                continue;
+            }
+            tokens.push(Token::Number(chars[start..i].iter().collect()));
+        } else if ch.is_alphabetic() {
+            let start = i;
+            while i < chars.len() && chars[i].is_alphabetic() {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            tokens.push(Token::Word(word.to_lowercase()));
+        } else {
+            if ch == '/' {
+                tokens.push(Token::Slash);
+            }
+            i += 1;
+        }
+    }
+
+    tokens
+}
+
+/// Returns the index just after the number starting at `start`.
+fn scan_number(chars: &[char], start: usize) -> usize {
+    let digits_from = |i: usize| chars[i..].iter().take_while(|c| c.is_ascii_digit()).count();
+
+    let mut i = start + digits_from(start);
+    while let Some(&sep) = chars.get(i) {
+        let group_len = if i + 1 < chars.len() {
+            digits_from(i + 1)
+        } else {
+            0
+        };
+        let accepted = match sep {
+            ',' | '.' | '\'' => group_len > 0,
+            // spaces are only thousand separators if exactly three digits follow them,
+            // otherwise they're just separating two numbers
+            ' ' | '\u{a0}' | '\u{202f}' => group_len == 3,
+            _ => false,
+        };
+        if !accepted {
+            break;
+        }
+        i += 1 + group_len;
+    }
+    i
+}
+
+fn scan_time(chars: &[char], colon: usize) -> usize {
+    let mut i = colon;
+    while chars.get(i) == Some(&':') && chars.get(i + 1).is_some_and(char::is_ascii_digit) {
+        i += 1;
+        while chars.get(i).is_some_and(char::is_ascii_digit) {
+            i += 1;
+        }
+    }
+    i
+}
+
+fn time_from_str(time_str: &str) -> Option<u32> {
+    let parts: Vec<u32> = time_str
+        .split(':')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    if parts.len() > 3 || parts[1..].iter().any(|&part| part >= 60) {
+        return None;
+    }
+    parts
+        .iter()
+        .try_fold(0u32, |acc, &part| acc.checked_mul(60)?.checked_add(part))
+}
+
+/// Parses a number where any separator followed by exactly three digits is a thousand
+/// separator, and a separator followed by any other number of digits is a decimal point.
+fn parse_number(raw: &str) -> Result<f64, ParseError> {
+    let invalid = || ParseError::InvalidNumber(raw.to_owned());
+
+    let mut groups = raw.split(|c: char| !c.is_ascii_digit());
+    let mut digits = groups.next().ok_or_else(invalid)?.to_owned();
+    let mut decimals = None;
+    for group in groups {
+        if decimals.is_some() {
+            return Err(invalid());
+        }
+        if group.len() == 3 {
+            digits.push_str(group);
+        } else {
+            decimals = Some(group);
+        }
+    }
+
+    let number = match decimals {
+        Some(decimals) => format!("{digits}.{decimals}"),
+        None => digits,
+    };
+    number.parse::<f64>().map_err(|_| invalid())
+}
+
+fn small_int(value: f64, raw: &str) -> Result<u8, ParseError> {
+    if value.fract() != 0.0 || value > f64::from(u8::MAX) {
+        return Err(ParseError::InvalidNumber(raw.to_owned()));
+    }
+    Ok(value as u8)
+}
+
+fn is_round_word(word: &str) -> bool {
+    matches!(
+        word,
+        "round" | "manche" | "rodada" | "ronda" | "runde" | "turno" | "ronde"
+    )
+}
+
+fn is_points_word(word: &str) -> bool {
+    matches!(
+        word,
+        "points"
+            | "point"
+            | "pts"
+            | "pt"
+            | "punkte"
+            | "pkt"
+            | "puntos"
+            | "pontos"
+            | "punti"
+            | "punten"
+            | "poäng"
+            | "poeng"
+    )
+}
+
+fn distance_unit(word: &str) -> Option<DistanceUnit> {
+    match word {
+        "m" | "metres" | "meters" | "metros" | "mètres" | "meter" => Some(DistanceUnit::Metres),
+        "km" | "kilometres" | "kilometers" | "kilomètres" | "quilômetros" => {
+            Some(DistanceUnit::Kilometres)
+        }
+        "ft" | "feet" => Some(DistanceUnit::Feet),
+        "yd" | "yds" | "yards" => Some(DistanceUnit::Yards),
+        "mi" | "miles" | "mile" => Some(DistanceUnit::Miles),
+        _ => None,
+    }
+}
+
+/// Returns how many seconds one of the unit is.
+fn time_unit(word: &str) -> Option<u32> {
+    match word {
+        "s" | "sec" | "secs" | "second" | "seconds" | "secondes" | "segundos" => Some(1),
+        "min" | "mins" | "minute" | "minutes" | "minutos" => Some(60),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_score_test() {
+        assert_eq!(score_from_str("4,987\r\n"), Ok(4987));
+        assert_eq!(score_from_str("4 987"), Ok(4987));
+        assert_eq!(score_from_str("4.987"), Ok(4987));
+        assert_eq!(score_from_str("4\u{202f}987 points"), Ok(4987));
+        assert_eq!(score_from_str("5000"), Ok(5000));
+        assert_eq!(score_from_str("0"), Ok(0));
+        assert!(is_new_game_command("New game\r\n"));
+        assert!(!is_new_game_command("5000 points"));
+    }
+
+    #[test]
+    fn result_screen_test() {
+        let txt = "Round 3/5\n4,987 points\nYour guess was 1.2 km from the correct location\n0:42";
+        let res = parse_round_result(txt).unwrap();
+        assert_eq!(res.score, 4987);
+        assert_eq!(
+            res.round,
+            Some(RoundIndex {
+                number: 3,
+                out_of: Some(5)
+            })
+        );
+        assert_eq!(res.time_taken_secs, Some(42));
+        let distance = res.distance.unwrap();
+        assert_eq!(distance.unit, DistanceUnit::Kilometres);
+        assert!((distance.in_metres() - 1200.0).abs() < 1e-9);
+
+        let txt = "Manche 2 / 5   3 412 pts   1 234 km   1 min 5 sec";
+        let res = parse_round_result(txt).unwrap();
+        assert_eq!(res.score, 3412);
+        assert_eq!(
+            res.round,
+            Some(RoundIndex {
+                number: 2,
+                out_of: Some(5)
+            })
+        );
+        assert_eq!(
+            res.distance,
+            Some(Distance {
+                value: 1234.0,
+                unit: DistanceUnit::Kilometres
+            })
+        );
+        assert_eq!(res.time_taken_secs, Some(65));
+    }
+
+    #[test]
+    fn parse_errors_test() {
+        assert_eq!(score_from_str(""), Err(ParseError::Empty));
+        assert_eq!(score_from_str(" \n "), Err(ParseError::Empty));
+        assert_eq!(score_from_str("12 km"), Err(ParseError::MissingScore));
+        assert_eq!(
+            score_from_str("5001"),
+            Err(ParseError::ScoreOutOfRange(5001))
+        );
+        assert_eq!(
+            score_from_str("12.5 points"),
+            Err(ParseError::InvalidNumber("12.5".to_owned()))
+        );
+        assert_eq!(
+            score_from_str("1.2.3"),
+            Err(ParseError::InvalidNumber("1.2.3".to_owned()))
+        );
+        assert_eq!(
+            parse_round_result("Round 3 / 500\n4,987 points"),
+            Err(ParseError::InvalidNumber("500".to_owned()))
+        );
+    }
+
+    #[test]
+    fn oversized_time_test() {
+        assert_eq!(time_from_str("1193046:28:15"), Some(u32::MAX));
+        assert_eq!(time_from_str("99999999:59:59"), None);
+        for txt in [
+            "4,987 points 99999999:59:59",
+            "4,987 points 99999999:99:99",
+            "4,987 points 5000000000 sec",
+            "4,987 points 1193046:28:15 0:01",
+        ] {
+            let res = parse_round_result(txt).unwrap();
+            assert_eq!(res.score, 4987);
+            assert_eq!(res.time_taken_secs, None);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Playing back a saved marathon, to see how it went over time.
+
+use std::time::Duration;
+
+use crate::marathon_log::{MarathonLog, Snapshot};
+use crate::metadata::RunMetadata;
+
+pub const MIN_SPEED: f64 = 1.0;
+pub const MAX_SPEED: f64 = 1000.0;
+
+/// A position in a finished marathon that moves forward at some multiple of real time.
+pub struct Replay {
+    marathon_log: MarathonLog,
+    position_secs: f64,
+    speed: f64,
+    is_playing: bool,
+}
+
+impl Replay {
+    /// Starts a paused replay at the beginning of `marathon_log`.
+    pub fn new(marathon_log: MarathonLog) -> Self {
+        Replay {
+            marathon_log,
+            position_secs: 0.0,
+            speed: MIN_SPEED,
+            is_playing: false,
+        }
+    }
+
+    /// Moves the replay forward by `real_time` times the speed, if it's playing. The replay
+    /// pauses itself when it reaches the end of the marathon.
+    pub fn advance(&mut self, real_time: Duration) {
+        if !self.is_playing {
+            return;
+        }
+        let duration = f64::from(self.duration_secs());
+        self.position_secs += real_time.as_secs_f64() * self.speed;
+        if self.position_secs >= duration {
+            self.position_secs = duration;
+            self.is_playing = false;
+        }
+    }
+
+    /// Jumps to `secs` into the marathon.
+    pub fn seek(&mut self, secs: u32) {
+        self.position_secs = f64::from(secs.min(self.duration_secs()));
+    }
+
+    pub fn metadata(&self) -> &RunMetadata {
+        self.marathon_log.metadata()
+    }
+
+    pub fn position_secs(&self) -> u32 {
+        self.position_secs as u32
+    }
+
+    pub fn duration_secs(&self) -> u32 {
+        self.marathon_log.duration_secs()
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Sets how many seconds of the marathon pass per real second, between 1 and 1000.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.is_playing
+    }
+
+    /// Starts or stops the replay. Playing from the end starts again from the beginning.
+    pub fn toggle_playing(&mut self) {
+        if !self.is_playing && self.position_secs() >= self.duration_secs() {
+            self.position_secs = 0.0;
+        }
+        self.is_playing = !self.is_playing;
+    }
+
+    /// What the tracker showed at the current position.
+    pub fn snapshot(&self) -> Snapshot {
+        self.marathon_log.snapshot_at(self.position_secs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marathon_log::{Entry, EntryKind, EntryTiming};
+
+    fn entry(score: u16, time_secs: u32) -> Entry {
+        Entry {
+            score,
+            time_secs,
+            kind: EntryKind::Guess,
+            player: 0,
+            starts_game: false,
+            timing: EntryTiming::OnTime,
+        }
+    }
+
+    #[test]
+    fn replay_test() {
+        let entries = [entry(5000, 100), entry(3000, 200), entry(5000, 400)];
+        let mut replay = Replay::new(MarathonLog::from_entries(1000, entries));
+        replay.advance(Duration::from_secs(10));
+        assert_eq!(replay.position_secs(), 0);
+
+        replay.set_speed(20.0);
+        replay.toggle_playing();
+        replay.advance(Duration::from_secs(15));
+        let snapshot = replay.snapshot();
+        assert_eq!(snapshot.elapsed_secs, 300);
+        assert_eq!(snapshot.count, 1);
+        assert_eq!(snapshot.time_since_last_hit, Some(200));
+        assert_eq!(snapshot.estimated_pace, Some(3));
+
+        replay.seek(400);
+        assert_eq!(replay.snapshot().count, 2);
+        assert_eq!(replay.snapshot().time_since_last_hit, Some(0));
+
+        replay.set_speed(5000.0);
+        assert_eq!(replay.speed(), MAX_SPEED);
+        replay.advance(Duration::from_secs(1));
+        assert_eq!(replay.position_secs(), 1000);
+        assert!(!replay.is_playing());
+        assert_eq!(replay.snapshot().time_left_secs(), 0);
+    }
+}
@@ -0,0 +1,284 @@
+//! A self-contained HTML report of a run, with the charts drawn as inline SVG so the page can be
+//! opened and shared without a network connection. The report is written in the current language.
+
+use std::fmt::Write as _;
+use std::fs;
+
+use crate::analysis::{droughts, Drought};
+use crate::charts::{cumulative_chart, score_chart};
+use crate::error::PersistError;
+use crate::headline::headline_stats;
+use crate::i18n::{fmt_decimal, fmt_int, fmt_percent, language, tr, trf};
+use crate::marathon_log::MarathonLog;
+use crate::splits::{log_splits, HOUR_SECS};
+use crate::utils::time_counter;
+
+/// How many of the longest droughts are listed.
+const TOP_DROUGHTS: usize = 5;
+
+const STYLE: &str = "body{font-family:sans-serif;max-width:800px;margin:2em auto;color:#222}\
+    table{border-collapse:collapse;margin-bottom:1em}\
+    td,th{border:1px solid #ccc;padding:2px 8px;text-align:right}\
+    th{background:#f0f0f0}td.text{text-align:left}\
+    .headline{display:flex;flex-wrap:wrap;gap:1em}\
+    .headline div{border:1px solid #ccc;padding:0.5em 1em}\
+    .headline b{display:block;font-size:1.4em}svg{max-width:100%;height:auto}";
+
+/// The whole report as an HTML page.
+pub fn html_report(marathon_log: &MarathonLog) -> String {
+    let metadata = marathon_log.metadata();
+    let title = match metadata.player.trim() {
+        "" => tr("Marathon report"),
+        player => trf("Marathon report: {0}", &[&player]),
+    };
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+        <style>{STYLE}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        language().code(),
+        escape_html(&title),
+        escape_html(&title)
+    );
+    html += &metadata_section(marathon_log);
+    html += &headline_section(marathon_log);
+    let count_label = marathon_log.scoring_rule().count_label();
+    html += &format!("<h2>{}</h2>\n", escape_html(&count_label));
+    html += &cumulative_chart(marathon_log, None).to_svg();
+    html += &splits_section(marathon_log);
+    html += &format!("<h2>{}</h2>\n", escape_html(&tr("Score distribution")));
+    html += &score_chart(marathon_log).to_svg();
+    html += &droughts_section(marathon_log);
+    html += &pauses_section(marathon_log);
+    html += "</body>\n</html>\n";
+    html
+}
+
+/// Writes `html` to `path`, replacing the file if it exists.
+pub fn write_report(path: &str, html: &str) -> Result<(), PersistError> {
+    fs::write(path, html).map_err(|err| PersistError::io(path, err))
+}
+
+fn metadata_section(marathon_log: &MarathonLog) -> String {
+    let metadata = marathon_log.metadata();
+    let players = marathon_log
+        .player_names()
+        .iter()
+        .filter(|name| !name.is_empty())
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    let rows = [
+        ("Player", metadata.player.clone()),
+        ("Players", players),
+        ("Map", metadata.map_name.clone()),
+        ("Map ID", metadata.map_id.clone()),
+        ("Game mode", tr(&metadata.game_mode.to_string())),
+        ("Scoring", marathon_log.scoring_rule().name()),
+        ("Target", metadata.target.map_or(String::new(), fmt_int)),
+        ("Rules", metadata.rules.clone()),
+        ("Started", metadata.started_at_txt()),
+        ("Setup", metadata.setup.clone()),
+        ("Notes", metadata.notes.clone()),
+    ];
+    let mut section = format!("<h2>{}</h2>\n<table>\n", escape_html(&tr("Run details")));
+    for (name, value) in rows.iter().filter(|(_, value)| !value.is_empty()) {
+        let _ = writeln!(
+            section,
+            "<tr><th>{}</th><td class=\"text\">{}</td></tr>",
+            escape_html(&tr(name)),
+            escape_html(value)
+        );
+    }
+    section += "</table>\n";
+    section
+}
+
+fn headline_section(marathon_log: &MarathonLog) -> String {
+    let rows = headline_stats(marathon_log).rows(&marathon_log.scoring_rule());
+    let mut section = format!(
+        "<h2>{}</h2>\n<div class=\"headline\">\n",
+        escape_html(&tr("Summary"))
+    );
+    for (name, value) in rows {
+        let _ = writeln!(
+            section,
+            "<div>{}<b>{}</b></div>",
+            escape_html(&name),
+            escape_html(&value)
+        );
+    }
+    section += "</div>\n";
+    section
+}
+
+fn splits_section(marathon_log: &MarathonLog) -> String {
+    let end_secs = marathon_log.played_secs();
+    // whole hours, so the last entry falls inside the last split
+    let end_secs = (end_secs / HOUR_SECS + 1) * HOUR_SECS;
+    let end_secs = end_secs.min(marathon_log.duration_secs().max(1));
+    let comparisons = log_splits(marathon_log, HOUR_SECS, end_secs, None);
+    let has_target = comparisons
+        .iter()
+        .any(|comparison| comparison.target_cumulative.is_some());
+    let mut section = format!("<h2>{}</h2>\n<table>\n", escape_html(&tr("Hourly splits")));
+    let mut headers = vec!["Hour", "Count", "Total", "Rounds", "Hit rate", "Average"];
+    if has_target {
+        headers.push("vs target");
+    }
+    section += &header_row(&headers);
+    for comparison in &comparisons {
+        let split = &comparison.split;
+        let _ = write!(
+            section,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>",
+            fmt_int(split.start_secs / HOUR_SECS + 1),
+            fmt_int(split.count),
+            fmt_int(split.cumulative_count),
+            fmt_int(split.rounds),
+            split.hit_rate().map_or("-".to_owned(), fmt_percent),
+            split
+                .mean_score()
+                .map_or("-".to_owned(), |mean| fmt_decimal(mean, 0)),
+        );
+        if has_target {
+            let vs_target = comparison.vs_target().map_or("-".to_owned(), |diff| {
+                let sign = if diff > 0.0 { "+" } else { "" };
+                format!("{sign}{}", fmt_decimal(diff, 1))
+            });
+            let _ = write!(section, "<td>{vs_target}</td>");
+        }
+        section += "</tr>\n";
+    }
+    section += "</table>\n";
+    section
+}
+
+/// The droughts of the run, longest first.
+fn longest_droughts(marathon_log: &MarathonLog) -> Vec<Drought> {
+    let rule = marathon_log.scoring_rule();
+    let mut droughts = droughts(marathon_log.entries(), &rule, marathon_log.played_secs());
+    droughts.sort_by(|a, b| {
+        b.secs()
+            .cmp(&a.secs())
+            .then(a.start_secs.cmp(&b.start_secs))
+    });
+    droughts
+}
+
+fn droughts_section(marathon_log: &MarathonLog) -> String {
+    let droughts = longest_droughts(marathon_log);
+    if droughts.is_empty() {
+        return String::new();
+    }
+    let mut section = format!(
+        "<h2>{}</h2>\n<table>\n",
+        escape_html(&tr("Longest droughts"))
+    );
+    section += &header_row(&["From", "To", "Length", "Rounds"]);
+    for drought in droughts.iter().take(TOP_DROUGHTS) {
+        let _ = writeln!(
+            section,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            time_counter(drought.start_secs),
+            time_counter(drought.end_secs),
+            time_counter(drought.secs()),
+            fmt_int(drought.rounds)
+        );
+    }
+    section += "</table>\n";
+    section
+}
+
+fn pauses_section(marathon_log: &MarathonLog) -> String {
+    let pauses = marathon_log.pauses();
+    if pauses.is_empty() {
+        return String::new();
+    }
+    let mut section = format!("<h2>{}</h2>\n<table>\n", escape_html(&tr("Pauses")));
+    section += &header_row(&["At", "Length"]);
+    for pause in pauses {
+        let _ = writeln!(
+            section,
+            "<tr><td>{}</td><td>{}</td></tr>",
+            time_counter(pause.time_secs),
+            time_counter(pause.real_secs)
+        );
+    }
+    section += "</table>\n";
+    section
+}
+
+/// A table row of `headers`, translated.
+fn header_row(headers: &[&str]) -> String {
+    let mut row = "<tr>".to_owned();
+    for header in headers {
+        let _ = write!(row, "<th>{}</th>", escape_html(&tr(header)));
+    }
+    row += "</tr>\n";
+    row
+}
+
+/// Escapes the characters that have a meaning in HTML text and attributes.
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped += "&amp;",
+            '<' => escaped += "&lt;",
+            '>' => escaped += "&gt;",
+            '"' => escaped += "&quot;",
+            '\'' => escaped += "&#39;",
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marathon_log::{Entry, EntryKind, EntryTiming, Pause};
+
+    #[test]
+    fn report_test() {
+        let entries = [
+            (5000, 30, EntryKind::Guess),
+            (1200, 70, EntryKind::Guess),
+            (0, 80, EntryKind::Skipped),
+            (4999, 4000, EntryKind::Guess),
+            (5000, 4100, EntryKind::Guess),
+        ]
+        .map(|(score, time_secs, kind)| Entry {
+            score,
+            time_secs,
+            kind,
+            player: 0,
+            starts_game: false,
+            timing: EntryTiming::OnTime,
+        });
+        let mut entries = entries.to_vec();
+        entries.push(Entry {
+            timing: EntryTiming::Grace { counted: false },
+            ..entries[4]
+        });
+        let mut marathon_log = MarathonLog::from_entries(8000, entries);
+        marathon_log.metadata_mut().player = "Ana <&>".to_owned();
+        marathon_log.metadata_mut().target = Some(10);
+        marathon_log.insert_pause(Pause {
+            time_secs: 100,
+            real_secs: 600,
+        });
+
+        assert_eq!(longest_droughts(&marathon_log)[0].secs(), 4070);
+
+        let html = html_report(&marathon_log);
+        assert!(html.contains("Ana &lt;&amp;&gt;"));
+        assert!(!html.contains("Ana <&>"));
+        assert!(html.contains("<svg"));
+        // two hours of splits, with the target compared
+        assert_eq!(html.matches("<tr><td>").count(), 2 + 2 + 1);
+        assert!(html.contains("vs target"));
+        assert!(html.contains("00:10:00"));
+        assert!(html.contains("Grace entries<b>1 (0 counted)</b>"));
+        assert!(!html.contains("Late entries"));
+    }
+}
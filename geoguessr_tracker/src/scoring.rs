@@ -0,0 +1,162 @@
+//! What a marathon counts, e.g. 5ks, rounds over some score, total points or the longest streak.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{tr, trf};
+use crate::marathon_log::{Entry, EntryKind};
+
+/// How entries add up to the number a marathon is trying to get as high as possible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScoringRule {
+    /// Counts guesses scoring at least `min_score`, 5000 for the usual "count 5ks" marathon.
+    Threshold { min_score: u16 },
+    /// Adds up the points of every guess.
+    TotalPoints,
+    /// The longest run of guesses scoring at least `min_score`. A guess under `min_score` or a
+    /// round without a guess ends a streak, skipped games don't.
+    ///
+    /// This is a streak of scores, not a country streak: the result screen text doesn't say
+    /// whether the country was right, so the tracker has no way of knowing.
+    Streak { min_score: u16 },
+}
+
+impl Default for ScoringRule {
+    fn default() -> Self {
+        ScoringRule::Threshold { min_score: 5000 }
+    }
+}
+
+impl ScoringRule {
+    /// Whether `entry` is a hit, i.e. what the "last 5k" timer counts from. For total points this
+    /// is a 5k.
+    pub fn is_hit(&self, entry: &Entry) -> bool {
+        let min_score = match self {
+            ScoringRule::Threshold { min_score } | ScoringRule::Streak { min_score } => *min_score,
+            ScoringRule::TotalPoints => 5000,
+        };
+        entry.kind == EntryKind::Guess && entry.score >= min_score
+    }
+
+    /// Whether the count can be projected to the end of the marathon by keeping up the rate so
+    /// far, which isn't the case for streaks.
+    pub fn is_projectable(&self) -> bool {
+        !matches!(self, ScoringRule::Streak { .. })
+    }
+
+    /// Adds up the count of `entries`, which should be in time order.
+    pub fn count(&self, entries: impl IntoIterator<Item = Entry>) -> u32 {
+        let mut tally = Tally::default();
+        for entry in entries {
+            tally.add(self, &entry);
+        }
+        tally.count
+    }
+
+    /// The rule's name in the current language, e.g. "Count 5ks". `Display` gives it in English.
+    pub fn name(&self) -> String {
+        match self {
+            ScoringRule::Threshold { .. } => trf("Count {0}s", &[&self.hit_name()]),
+            ScoringRule::TotalPoints => tr("Total points"),
+            ScoringRule::Streak { .. } => trf("{0} streak", &[&self.hit_name()]),
+        }
+    }
+
+    /// What the count is called in the current language, e.g. "5k count".
+    pub fn count_label(&self) -> String {
+        match self {
+            ScoringRule::Threshold { .. } => trf("{0} count", &[&self.hit_name()]),
+            ScoringRule::TotalPoints => tr("Points"),
+            ScoringRule::Streak { .. } => tr("Best streak"),
+        }
+    }
+
+    /// What a hit is called, e.g. "5k" or "4900+".
+    pub fn hit_name(&self) -> String {
+        match self {
+            ScoringRule::Threshold { min_score: 5000 }
+            | ScoringRule::Streak { min_score: 5000 }
+            | ScoringRule::TotalPoints => "5k".to_owned(),
+            ScoringRule::Threshold { min_score } | ScoringRule::Streak { min_score } => {
+                format!("{min_score}+")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ScoringRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScoringRule::Threshold { .. } => write!(f, "Count {}s", self.hit_name()),
+            ScoringRule::TotalPoints => f.write_str("Total points"),
+            ScoringRule::Streak { .. } => write!(f, "{} streak", self.hit_name()),
+        }
+    }
+}
+
+/// A running count under some [`ScoringRule`], for adding entries one at a time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub count: u32,
+    /// The streak still going at the last entry, only used for [`ScoringRule::Streak`].
+    pub current_streak: u32,
+}
+
+impl Tally {
+    /// Counts `entry`, which has to be later than every entry counted so far. Late entries, and
+    /// grace entries that don't count, are skipped.
+    pub fn add(&mut self, rule: &ScoringRule, entry: &Entry) {
+        if !entry.timing.counts() {
+            return;
+        }
+        match rule {
+            ScoringRule::Threshold { .. } => self.count += u32::from(rule.is_hit(entry)),
+            ScoringRule::TotalPoints => self.count += u32::from(entry.score),
+            ScoringRule::Streak { .. } => {
+                if rule.is_hit(entry) {
+                    self.current_streak += 1;
+                    self.count = self.count.max(self.current_streak);
+                } else if entry.kind != EntryKind::Skipped {
+                    self.current_streak = 0;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marathon_log::EntryTiming;
+
+    #[test]
+    fn scoring_rule_test() {
+        let entries = [
+            (5000, EntryKind::Guess),
+            (4950, EntryKind::Guess),
+            (0, EntryKind::Skipped),
+            (5000, EntryKind::Guess),
+            (0, EntryKind::NoGuess),
+            (4999, EntryKind::Guess),
+        ]
+        .map(|(score, kind)| Entry {
+            score,
+            time_secs: 0,
+            kind,
+            player: 0,
+            starts_game: false,
+            timing: EntryTiming::OnTime,
+        });
+        let rule = ScoringRule::default();
+        assert_eq!(rule.count(entries), 2);
+        assert_eq!(rule.count_label(), "5k count");
+        let rule = ScoringRule::Threshold { min_score: 4900 };
+        assert_eq!(rule.count(entries), 4);
+        assert_eq!(rule.count_label(), "4900+ count");
+        assert_eq!(ScoringRule::TotalPoints.count(entries), 19_949);
+        assert!(!ScoringRule::Streak { min_score: 0 }.is_projectable());
+        assert_eq!(ScoringRule::Streak { min_score: 4900 }.count(entries), 3);
+        assert_eq!(ScoringRule::Streak { min_score: 5000 }.count(entries), 1);
+    }
+}
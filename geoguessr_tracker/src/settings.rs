@@ -0,0 +1,128 @@
+//! Tracker settings that are kept between runs, saved as RON next to the logs.
+
+use std::fs;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::AlertSettings;
+use crate::analysis::DroughtThresholds;
+use crate::error::PersistError;
+use crate::i18n::Language;
+use crate::marathon_log::{GraceRule, LateEntryPolicy, PausePolicy};
+use crate::theme::ThemeSource;
+use crate::utils::timekeeping::TWENTY_FOUR_HOURS_IN_SECS;
+
+/// The settings are read from and saved to this file in the tracker's folder.
+pub const SETTINGS_FILE: &str = "settings.ron";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)] // so that settings can be added without breaking saved files
+pub struct Settings {
+    /// How long a marathon lasts.
+    pub duration_secs: u32,
+    pub late_entry_policy: LateEntryPolicy,
+    /// How long after the end entries are still taken, for finishing the round in progress.
+    pub grace_secs: u32,
+    pub grace_rule: GraceRule,
+    pub pause_policy: PausePolicy,
+    /// Whether the log is saved to a new file when the marathon ends.
+    pub auto_save_at_end: bool,
+    pub alerts: AlertSettings,
+    pub drought_thresholds: DroughtThresholds,
+    pub theme: ThemeSource,
+    pub language: Language,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            duration_secs: TWENTY_FOUR_HOURS_IN_SECS,
+            late_entry_policy: LateEntryPolicy::default(),
+            grace_secs: 0,
+            grace_rule: GraceRule::default(),
+            pause_policy: PausePolicy::default(),
+            auto_save_at_end: true,
+            alerts: AlertSettings::default(),
+            drought_thresholds: DroughtThresholds::default(),
+            theme: ThemeSource::default(),
+            language: Language::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load(path: &str) -> Result<Self, PersistError> {
+        if !fs::exists(path).map_err(|err| PersistError::io(path, err))? {
+            return Err(PersistError::NotFound { path: path.into() });
+        }
+        let contents = fs::read_to_string(path).map_err(|err| PersistError::io(path, err))?;
+        ron::from_str(&contents).map_err(|err| PersistError::Deserialize {
+            path: path.into(),
+            line: err.position.line,
+            col: err.position.col,
+            source: err.code,
+        })
+    }
+
+    /// The settings saved in `path`, or the defaults if there aren't any or they can't be read.
+    pub fn load_or_default(path: &str) -> Self {
+        match Settings::load(path) {
+            Ok(settings) => settings,
+            Err(PersistError::NotFound { .. }) => Settings::default(),
+            Err(err) => {
+                warn!("using the default settings: {err}");
+                Settings::default()
+            }
+        }
+    }
+
+    /// Saves the settings to `path`, replacing the file if it exists.
+    pub fn save(&self, path: &str) -> Result<(), PersistError> {
+        let serialized = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(PersistError::Serialize)?;
+        fs::write(path, serialized).map_err(|err| PersistError::io(path, err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_test() {
+        let path = std::env::temp_dir().join("geoguessr_tracker_settings.ron");
+        let path = path.to_str().unwrap();
+        let _ = fs::remove_file(path);
+        assert_eq!(Settings::load_or_default(path), Settings::default());
+
+        let settings = Settings {
+            duration_secs: 12 * 60 * 60,
+            late_entry_policy: LateEntryPolicy::Reject,
+            grace_secs: 90,
+            grace_rule: GraceRule::CountAll,
+            pause_policy: PausePolicy::Queue,
+            auto_save_at_end: false,
+            alerts: AlertSettings {
+                every_nth_hit: 25,
+                sound: true,
+                ..AlertSettings::default()
+            },
+            drought_thresholds: DroughtThresholds {
+                warning_mins: 5,
+                alarm_mins: 10,
+            },
+            theme: ThemeSource::File("themes/stream.ron".to_owned()),
+            language: Language::Portuguese,
+        };
+        settings.save(path).unwrap();
+        let loaded = Settings::load(path);
+        fs::write(path, "(duration_secs: 600)").unwrap();
+        let partial = Settings::load(path);
+        fs::remove_file(path).unwrap();
+        assert_eq!(loaded.unwrap(), settings);
+        let partial = partial.unwrap();
+        assert_eq!(partial.duration_secs, 600);
+        assert!(partial.auto_save_at_end);
+    }
+}
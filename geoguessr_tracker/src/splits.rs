@@ -0,0 +1,226 @@
+//! The marathon broken into intervals, usually hours, to see how each part of it went.
+
+use crate::marathon_log::{Entry, EntryKind, MarathonLog};
+use crate::scoring::{ScoringRule, Tally};
+
+pub const HOUR_SECS: u32 = 60 * 60;
+
+/// The entries in one interval of the marathon, from `start_secs` up to, but not including,
+/// `end_secs`. The last split includes `end_secs`, so entries added right at the end are in it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Split {
+    pub start_secs: u32,
+    pub end_secs: u32,
+    /// The count under the scoring rule, only counting entries in this interval.
+    pub count: u32,
+    /// The count from the start of the marathon to the end of this interval.
+    pub cumulative_count: u32,
+    /// Rounds played, not counting skipped games, or entries that don't add to the count.
+    pub rounds: u32,
+    pub hits: u32,
+    pub total_points: u64,
+}
+
+impl Split {
+    pub fn hit_rate(&self) -> Option<f64> {
+        (self.rounds > 0).then(|| f64::from(self.hits) / f64::from(self.rounds))
+    }
+
+    pub fn mean_score(&self) -> Option<f64> {
+        (self.rounds > 0).then(|| self.total_points as f64 / f64::from(self.rounds))
+    }
+}
+
+/// A split next to what was aimed for, and the same interval of an earlier run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SplitComparison {
+    pub split: Split,
+    /// Where the cumulative count should be by the end of the split to reach the target.
+    pub target_cumulative: Option<f64>,
+    /// The earlier run's split covering the same interval.
+    pub previous: Option<Split>,
+}
+
+impl SplitComparison {
+    /// How far the cumulative count is ahead of the target, or behind if negative.
+    pub fn vs_target(&self) -> Option<f64> {
+        let target = self.target_cumulative?;
+        Some(f64::from(self.split.cumulative_count) - target)
+    }
+
+    /// How far the cumulative count is ahead of the earlier run, or behind if negative.
+    pub fn vs_previous(&self) -> Option<i64> {
+        let previous = self.previous?;
+        Some(i64::from(self.split.cumulative_count) - i64::from(previous.cumulative_count))
+    }
+}
+
+/// Splits `entries`, which should be in time order, into intervals of `interval_secs` from the
+/// start of the marathon up to `end_secs`. The last split is shorter if the intervals don't fit
+/// exactly, and takes entries at `end_secs` too, e.g. grace entries added after the end, which
+/// are timed at the end. Entries that don't add to the count, like late ones, are left out.
+pub fn compute_splits(
+    entries: impl IntoIterator<Item = Entry>,
+    rule: &ScoringRule,
+    interval_secs: u32,
+    end_secs: u32,
+) -> Vec<Split> {
+    let interval_secs = interval_secs.max(1);
+    let mut entries = entries.into_iter().peekable();
+    let mut splits = Vec::new();
+    let mut cumulative = Tally::default();
+    let mut start_secs = 0;
+    while start_secs < end_secs {
+        let split_end = start_secs.saturating_add(interval_secs).min(end_secs);
+        let mut split = Split {
+            start_secs,
+            end_secs: split_end,
+            count: 0,
+            cumulative_count: 0,
+            rounds: 0,
+            hits: 0,
+            total_points: 0,
+        };
+        let mut split_entries = Vec::new();
+        let is_last = split_end == end_secs;
+        while let Some(entry) = entries
+            .next_if(|entry| entry.time_secs < split_end || is_last && entry.time_secs == end_secs)
+        {
+            if !entry.timing.counts() {
+                continue;
+            }
+            if entry.kind != EntryKind::Skipped {
+                split.rounds += 1;
+                split.hits += u32::from(rule.is_hit(&entry));
+                split.total_points += u64::from(entry.score);
+            }
+            cumulative.add(rule, &entry);
+            split_entries.push(entry);
+        }
+        split.count = rule.count(split_entries);
+        split.cumulative_count = cumulative.count;
+        splits.push(split);
+        start_secs = split_end;
+    }
+    splits
+}
+
+/// Lines `splits` up with `target`, the count aimed for by `duration_secs`, and with the splits
+/// of an earlier run.
+pub fn compare_splits(
+    splits: &[Split],
+    target: Option<u32>,
+    duration_secs: u32,
+    previous: &[Split],
+) -> Vec<SplitComparison> {
+    splits
+        .iter()
+        .map(|split| SplitComparison {
+            split: *split,
+            target_cumulative: target.filter(|_| duration_secs > 0).map(|target| {
+                f64::from(target) * f64::from(split.end_secs) / f64::from(duration_secs)
+            }),
+            previous: previous
+                .iter()
+                .find(|previous| previous.start_secs == split.start_secs)
+                .copied(),
+        })
+        .collect()
+}
+
+/// The splits of `marathon_log` up to `end_secs`, compared with its target and with `previous`.
+/// The earlier run is counted under the same scoring rule as `marathon_log`.
+pub fn log_splits(
+    marathon_log: &MarathonLog,
+    interval_secs: u32,
+    end_secs: u32,
+    previous: Option<&MarathonLog>,
+) -> Vec<SplitComparison> {
+    let rule = marathon_log.scoring_rule();
+    let splits = compute_splits(marathon_log.entries(), &rule, interval_secs, end_secs);
+    let previous_splits = previous.map_or(Vec::new(), |previous| {
+        let previous_end = previous.duration_secs().max(end_secs);
+        compute_splits(previous.entries(), &rule, interval_secs, previous_end)
+    });
+    compare_splits(
+        &splits,
+        marathon_log.metadata().target,
+        marathon_log.duration_secs(),
+        &previous_splits,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::marathon_log::EntryTiming;
+
+    fn entry(score: u16, time_secs: u32) -> Entry {
+        Entry {
+            score,
+            time_secs,
+            kind: EntryKind::Guess,
+            player: 0,
+            starts_game: false,
+            timing: EntryTiming::OnTime,
+        }
+    }
+
+    #[test]
+    fn splits_test() {
+        let entries = [
+            entry(5000, 10),
+            entry(4000, 50),
+            entry(5000, 100),
+            entry(5000, 250),
+            entry(5000, 260),
+        ];
+        let rule = ScoringRule::default();
+        let splits = compute_splits(entries, &rule, 100, 250);
+        let counts: Vec<(u32, u32, u32)> = splits
+            .iter()
+            .map(|split| (split.count, split.cumulative_count, split.rounds))
+            .collect();
+        // the 5k at 250 is right at the end, the one at 260 is after it
+        assert_eq!(counts, [(1, 1, 2), (1, 2, 1), (1, 3, 1)]);
+        assert_eq!(splits[2].end_secs, 250);
+        assert_eq!(splits[0].mean_score(), Some(4500.0));
+        assert_eq!(splits[1].hit_rate(), Some(1.0));
+        assert_eq!(compute_splits(entries, &rule, 100, 400)[3].hit_rate(), None);
+
+        let streak = ScoringRule::Streak { min_score: 5000 };
+        let streak_splits = compute_splits(entries, &streak, 100, 300);
+        assert_eq!(streak_splits[2].cumulative_count, 3);
+
+        let previous = compute_splits([entry(5000, 10)], &rule, 100, 1000);
+        let comparisons = compare_splits(&splits, Some(10), 1000, &previous);
+        assert_eq!(comparisons[0].vs_target(), Some(0.0));
+        assert_eq!(comparisons[1].vs_target(), Some(0.0));
+        assert_eq!(comparisons[1].vs_previous(), Some(1));
+        assert_eq!(
+            compare_splits(&splits, None, 1000, &[])[0].vs_target(),
+            None
+        );
+    }
+
+    #[test]
+    fn grace_splits_test() {
+        // entries after the end are timed at the end, whether they count or not
+        let at_end = |score, timing| Entry {
+            timing,
+            ..entry(score, 100)
+        };
+        let entries = [
+            entry(5000, 40),
+            at_end(5000, EntryTiming::Grace { counted: true }),
+            at_end(5000, EntryTiming::Grace { counted: false }),
+            at_end(5000, EntryTiming::Late),
+        ];
+        let marathon_log = MarathonLog::from_entries(100, entries);
+        let splits = log_splits(&marathon_log, 60, 100, None);
+        let last = splits[1].split;
+        assert_eq!((last.count, last.cumulative_count), (1, 2));
+        assert_eq!((last.rounds, last.hits, last.total_points), (1, 1, 5000));
+        assert_eq!(last.cumulative_count, marathon_log.count());
+    }
+}
@@ -0,0 +1,592 @@
+//! Sharing entries between trackers on a local network, for team marathons where each player
+//! runs their own tracker.
+//!
+//! One tracker is the host, and the others connect to it as clients over TCP. Clients push every
+//! entry they add to the host, which merges them into its own log under the client's player name.
+//! Messages are RON, one per line.
+//!
+//! Each client numbers its entries, and the host acknowledges the numbers it has merged into its
+//! log. Until then a client keeps its entries, and sends them again whenever it reconnects. The
+//! host ignores numbers it has already merged from the same client session, so entries are never
+//! lost or counted twice. A new [`SyncClient`] starts a new session, so its numbers start over.
+//!
+//! The host can also turn an entry away, e.g. when its timer is paused and its pause policy
+//! rejects entries. It tells the client instead of acknowledging the entry, and the client counts
+//! it in [`SyncClient::rejected_count`] so the player knows it wasn't counted.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::clock::Clock;
+use crate::marathon_log::{AddEntryResult, Entry, EntryKind, EntryTiming, MarathonLog};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const MIN_RECONNECT_DELAY: Duration = Duration::from_millis(100);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Message {
+    /// The first message from a client. `session` is the same for every connection of one
+    /// [`SyncClient`], and tells clients with the same player name apart.
+    Hello {
+        player_name: String,
+        #[serde(default)]
+        session: u64,
+    },
+    /// An entry added by a client, `seq` counts up from 0 for each client session.
+    Entry {
+        seq: u32,
+        score: u16,
+        time_secs: u32,
+        kind: EntryKind,
+        #[serde(default)]
+        starts_game: bool,
+        #[serde(default)]
+        timing: EntryTiming,
+    },
+    /// From the host, the entry with this number was merged into its log.
+    Ack { seq: u32 },
+    /// From the host, the entry with this number was turned away, so it isn't counted.
+    Reject { seq: u32 },
+}
+
+enum HostEvent {
+    Connected {
+        player_name: String,
+    },
+    Disconnected {
+        player_name: String,
+    },
+    Entry {
+        player_name: String,
+        session: u64,
+        seq: u32,
+        entry: Entry,
+        /// Where to send the host's answer for the entry.
+        replies: Sender<Message>,
+    },
+}
+
+/// What happened to an entry the host received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MergeOutcome {
+    Merged,
+    /// The entry was merged when it was sent before.
+    Duplicate,
+    /// The log didn't take the entry, e.g. because the timer is paused.
+    TurnedAway,
+}
+
+/// The tracker that every other tracker sends its entries to.
+pub struct SyncHost {
+    local_addr: SocketAddr,
+    events: Receiver<HostEvent>,
+    stop: Arc<AtomicBool>,
+    /// The entry numbers merged from each player name and client session.
+    merged: HashMap<(String, u64), HashSet<u32>>,
+    connected: HashMap<String, usize>,
+}
+
+impl SyncHost {
+    /// Starts listening for clients on `addr`, e.g. "0.0.0.0:7878".
+    pub fn start(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let local_addr = listener.local_addr()?;
+        let (event_tx, events) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let accept_stop = Arc::clone(&stop);
+        thread::spawn(move || accept_clients(listener, event_tx, accept_stop));
+        info!("sync host listening on {local_addr}");
+
+        Ok(SyncHost {
+            local_addr,
+            events,
+            stop,
+            merged: HashMap::new(),
+            connected: HashMap::new(),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// The names of the players that are connected right now.
+    pub fn connected_players(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.connected.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Merges every entry received since the last poll into `marathon_log`, under the name of the
+    /// player that sent it, and answers the clients. Returns how many entries were merged.
+    pub fn poll<C: Clock>(&mut self, marathon_log: &mut MarathonLog<C>) -> usize {
+        let mut merged_count = 0;
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                HostEvent::Connected { player_name } => {
+                    *self.connected.entry(player_name).or_default() += 1;
+                }
+                HostEvent::Disconnected { player_name } => {
+                    if let Some(count) = self.connected.get_mut(&player_name) {
+                        *count -= 1;
+                        if *count == 0 {
+                            self.connected.remove(&player_name);
+                        }
+                    }
+                }
+                HostEvent::Entry {
+                    player_name,
+                    session,
+                    seq,
+                    entry,
+                    replies,
+                } => {
+                    let outcome = self.merge(marathon_log, &player_name, session, seq, entry);
+                    if outcome == MergeOutcome::Merged {
+                        merged_count += 1;
+                    }
+                    let reply = match outcome {
+                        MergeOutcome::Merged | MergeOutcome::Duplicate => Message::Ack { seq },
+                        MergeOutcome::TurnedAway => Message::Reject { seq },
+                    };
+                    // the client may have disconnected since, it sends the entry again if so
+                    let _ = replies.send(reply);
+                }
+            }
+        }
+        merged_count
+    }
+
+    /// Adds an entry to the log unless it was already merged, going by the log's pause policy
+    /// and late entry policy like an entry added on this tracker. An entry that is queued until
+    /// the timer is resumed counts as merged, as it will be saved with the log.
+    fn merge<C: Clock>(
+        &mut self,
+        marathon_log: &mut MarathonLog<C>,
+        player_name: &str,
+        session: u64,
+        seq: u32,
+        entry: Entry,
+    ) -> MergeOutcome {
+        let key = (player_name.to_owned(), session);
+        if self
+            .merged
+            .get(&key)
+            .is_some_and(|merged| merged.contains(&seq))
+        {
+            return MergeOutcome::Duplicate;
+        }
+        let player = match marathon_log.find_or_add_player(player_name) {
+            Ok(player) => player,
+            Err(err) => {
+                warn!("turned away entry {seq} from {player_name}: {err}");
+                return MergeOutcome::TurnedAway;
+            }
+        };
+        let res = marathon_log.add_synced_entry(Entry { player, ..entry });
+        if matches!(res, AddEntryResult::Paused | AddEntryResult::MarathonOver) {
+            info!("turned away entry {seq} from {player_name}: {res:?}");
+            return MergeOutcome::TurnedAway;
+        }
+        self.merged.entry(key).or_default().insert(seq);
+        info!(
+            "synced entry {seq} from {player_name}: score {}, {res:?}",
+            entry.score
+        );
+        MergeOutcome::Merged
+    }
+}
+
+impl Drop for SyncHost {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+fn accept_clients(listener: TcpListener, event_tx: Sender<HostEvent>, stop: Arc<AtomicBool>) {
+    while !stop.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, addr)) => {
+                info!("sync client connected from {addr}");
+                let event_tx = event_tx.clone();
+                let stop = Arc::clone(&stop);
+                thread::spawn(move || {
+                    if let Err(err) = serve_client(stream, event_tx, stop) {
+                        warn!("sync client {addr} disconnected: {err}");
+                    }
+                });
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+            Err(err) => error!("error accepting sync client: {err}"),
+        }
+    }
+}
+
+fn serve_client(
+    stream: TcpStream,
+    event_tx: Sender<HostEvent>,
+    stop: Arc<AtomicBool>,
+) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = MessageReader::new(BufReader::new(stream));
+    let (reply_tx, replies) = mpsc::channel();
+
+    let mut player_name = None;
+    let mut session = 0;
+    let res = loop {
+        if stop.load(Ordering::SeqCst) {
+            break Ok(());
+        }
+        // the host answers each entry once it has been merged, or turned away
+        if let Err(err) = replies
+            .try_iter()
+            .try_for_each(|reply| write_message(&mut writer, &reply))
+        {
+            break Err(err);
+        }
+        let message = match reader.read_message() {
+            Ok(Some(message)) => message,
+            Ok(None) => break Ok(()),
+            Err(err) if is_timeout(&err) => continue,
+            Err(err) => break Err(err),
+        };
+        match (message, &player_name) {
+            (
+                Message::Hello {
+                    player_name: name,
+                    session: hello_session,
+                },
+                None,
+            ) => {
+                let _ = event_tx.send(HostEvent::Connected {
+                    player_name: name.clone(),
+                });
+                player_name = Some(name);
+                session = hello_session;
+            }
+            (
+                Message::Entry {
+                    seq,
+                    score,
+                    time_secs,
+                    kind,
+                    starts_game,
+                    timing,
+                },
+                Some(name),
+            ) => {
+                let entry = Entry {
+                    score,
+                    time_secs,
+                    kind,
+                    player: 0,
+                    starts_game,
+                    timing,
+                };
+                let event = HostEvent::Entry {
+                    player_name: name.clone(),
+                    session,
+                    seq,
+                    entry,
+                    replies: reply_tx.clone(),
+                };
+                if event_tx.send(event).is_err() {
+                    break Ok(()); // the host was dropped
+                }
+            }
+            (message, _) => warn!("unexpected sync message: {message:?}"),
+        }
+    };
+
+    if let Some(player_name) = player_name {
+        let _ = event_tx.send(HostEvent::Disconnected { player_name });
+    }
+    res
+}
+
+/// A tracker that sends its entries to a host, reconnecting whenever the connection drops.
+pub struct SyncClient {
+    entries: Sender<Entry>,
+    is_connected: Arc<AtomicBool>,
+    rejected: Arc<AtomicUsize>,
+}
+
+impl SyncClient {
+    /// Starts connecting to the host at `addr` in the background, as `player_name`.
+    pub fn connect(addr: impl Into<String>, player_name: impl Into<String>) -> Self {
+        let (entries, entry_rx) = mpsc::channel();
+        let is_connected = Arc::new(AtomicBool::new(false));
+        let rejected = Arc::new(AtomicUsize::new(0));
+        let mut connection = ClientConnection {
+            addr: addr.into(),
+            player_name: player_name.into(),
+            session: new_session_id(),
+            entries: entry_rx,
+            unacked: Vec::new(),
+            is_connected: Arc::clone(&is_connected),
+            rejected: Arc::clone(&rejected),
+        };
+        thread::spawn(move || connection.run());
+
+        SyncClient {
+            entries,
+            is_connected,
+            rejected,
+        }
+    }
+
+    /// Queues `entry` to be sent to the host.
+    pub fn send(&mut self, entry: Entry) {
+        // the connection thread only stops when the client is dropped, so this can't fail
+        let _ = self.entries.send(entry);
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.is_connected.load(Ordering::SeqCst)
+    }
+
+    /// How many entries the host turned away, so they aren't in the host's log.
+    pub fn rejected_count(&self) -> usize {
+        self.rejected.load(Ordering::SeqCst)
+    }
+}
+
+/// The state of the thread that talks to the host for a [`SyncClient`].
+struct ClientConnection {
+    addr: String,
+    player_name: String,
+    session: u64,
+    entries: Receiver<Entry>,
+    /// Entries, and their numbers, that the host hasn't acknowledged yet.
+    unacked: Vec<(u32, Entry)>,
+    is_connected: Arc<AtomicBool>,
+    rejected: Arc<AtomicUsize>,
+}
+
+impl ClientConnection {
+    fn run(&mut self) {
+        let mut next_seq = 0;
+        let mut reconnect_delay = MIN_RECONNECT_DELAY;
+        loop {
+            match self.connect_and_send(&mut next_seq) {
+                Ok(()) => return, // the client was dropped
+                Err(err) => {
+                    if self.is_connected.swap(false, Ordering::SeqCst) {
+                        reconnect_delay = MIN_RECONNECT_DELAY;
+                    }
+                    warn!("sync connection to {} failed: {err}", self.addr);
+                }
+            }
+            // keep queueing entries while waiting to reconnect
+            let wait_res = self.entries.recv_timeout(reconnect_delay);
+            match wait_res {
+                Ok(entry) => {
+                    self.unacked.push((next_seq, entry));
+                    next_seq += 1;
+                }
+                Err(RecvTimeoutError::Disconnected) => return,
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+            reconnect_delay = (reconnect_delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    }
+
+    /// Connects, sends anything unacknowledged, then sends new entries as they come in. Only
+    /// returns `Ok` once the client has been dropped.
+    fn connect_and_send(&mut self, next_seq: &mut u32) -> io::Result<()> {
+        let stream = TcpStream::connect(&self.addr)?;
+        stream.set_read_timeout(Some(POLL_INTERVAL))?;
+        let mut writer = stream.try_clone()?;
+        let mut reader = MessageReader::new(BufReader::new(stream));
+
+        let hello = Message::Hello {
+            player_name: self.player_name.clone(),
+            session: self.session,
+        };
+        write_message(&mut writer, &hello)?;
+        for &(seq, entry) in &self.unacked {
+            write_message(&mut writer, &entry_message(seq, entry))?;
+        }
+        self.is_connected.store(true, Ordering::SeqCst);
+        info!("connected to sync host {}", self.addr);
+
+        loop {
+            loop {
+                match self.entries.try_recv() {
+                    Ok(entry) => {
+                        let seq = *next_seq;
+                        *next_seq += 1;
+                        self.unacked.push((seq, entry));
+                        write_message(&mut writer, &entry_message(seq, entry))?;
+                    }
+                    Err(mpsc::TryRecvError::Empty) => break,
+                    Err(mpsc::TryRecvError::Disconnected) => return Ok(()),
+                }
+            }
+            match reader.read_message() {
+                Ok(Some(Message::Ack { seq })) => {
+                    self.unacked.retain(|&(unacked, _)| unacked != seq)
+                }
+                Ok(Some(Message::Reject { seq })) => {
+                    warn!("sync host turned away entry {seq}");
+                    self.unacked.retain(|&(unacked, _)| unacked != seq);
+                    self.rejected.fetch_add(1, Ordering::SeqCst);
+                }
+                Ok(Some(message)) => warn!("unexpected sync message: {message:?}"),
+                Ok(None) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "host disconnected",
+                    ))
+                }
+                Err(err) if is_timeout(&err) => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/// An id for a new client session, which is very unlikely to be the same as an earlier one, even
+/// from another tracker.
+fn new_session_id() -> u64 {
+    static SESSIONS: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos() as u64);
+    let count = SESSIONS.fetch_add(1, Ordering::SeqCst);
+    nanos ^ u64::from(std::process::id()).rotate_left(32) ^ count.rotate_left(48)
+}
+
+fn entry_message(seq: u32, entry: Entry) -> Message {
+    Message::Entry {
+        seq,
+        score: entry.score,
+        time_secs: entry.time_secs,
+        kind: entry.kind,
+        starts_game: entry.starts_game,
+        timing: entry.timing,
+    }
+}
+
+fn write_message(writer: &mut impl Write, message: &Message) -> io::Result<()> {
+    let mut line =
+        ron::to_string(message).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    line.push('\n');
+    writer.write_all(line.as_bytes())
+}
+
+/// Reads messages from a stream with a read timeout. Part of a line that was read before a
+/// timeout is kept, so the rest of it can be read by the next call.
+struct MessageReader<R> {
+    reader: R,
+    line: String,
+}
+
+impl<R: BufRead> MessageReader<R> {
+    fn new(reader: R) -> Self {
+        MessageReader {
+            reader,
+            line: String::new(),
+        }
+    }
+
+    /// Reads a message, or `None` if the connection was closed.
+    fn read_message(&mut self) -> io::Result<Option<Message>> {
+        if self.reader.read_line(&mut self.line)? == 0 {
+            return Ok(None);
+        }
+        let res = ron::from_str(&self.line);
+        self.line.clear();
+        res.map(Some)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+fn is_timeout(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_ignores_duplicates_test() {
+        use MergeOutcome::*;
+
+        let mut host = SyncHost::start("127.0.0.1:0").unwrap();
+        let mut marathon_log = MarathonLog::new(1000);
+        marathon_log.resume();
+        let entry = |score, time_secs| Entry {
+            score,
+            time_secs,
+            kind: EntryKind::Guess,
+            player: 0,
+            starts_game: false,
+            timing: EntryTiming::OnTime,
+        };
+        let mut merge = |marathon_log: &mut MarathonLog, name, session, seq, entry| {
+            host.merge(marathon_log, name, session, seq, entry)
+        };
+        assert_eq!(
+            merge(&mut marathon_log, "Bea", 1, 0, entry(5000, 50)),
+            Merged
+        );
+        assert_eq!(
+            merge(&mut marathon_log, "Bea", 1, 1, entry(1000, 20)),
+            Merged
+        );
+        assert_eq!(
+            merge(&mut marathon_log, "Bea", 1, 0, entry(5000, 50)),
+            Duplicate
+        );
+        assert_eq!(
+            merge(&mut marathon_log, "Cy", 1, 0, entry(5000, 30)),
+            Merged
+        );
+        // a new session of the same player numbers its entries from 0 again
+        assert_eq!(
+            merge(&mut marathon_log, "Bea", 2, 0, entry(4000, 60)),
+            Merged
+        );
+
+        let entries: Vec<Entry> = marathon_log.entries().collect();
+        let times: Vec<u32> = entries.iter().map(|entry| entry.time_secs).collect();
+        assert_eq!(times, vec![20, 30, 50, 60]);
+        assert_eq!(marathon_log.player_names(), &["", "Bea", "Cy"]);
+        assert_eq!(entries[1].player, 2);
+        assert_eq!(marathon_log.count(), 2);
+
+        // an entry the pause policy turns away isn't marked as merged, so it can be sent again
+        marathon_log.pause().unwrap();
+        assert_eq!(
+            merge(&mut marathon_log, "Cy", 1, 1, entry(5000, 70)),
+            TurnedAway
+        );
+        assert_eq!(marathon_log.entries().count(), 4);
+        marathon_log.resume();
+        assert_eq!(
+            merge(&mut marathon_log, "Cy", 1, 1, entry(5000, 70)),
+            Merged
+        );
+        assert_eq!(marathon_log.entries().count(), 5);
+    }
+}
@@ -0,0 +1,205 @@
+//! Random but realistic marathon logs, for testing and benchmarks.
+//!
+//! Only available in tests, or with the `synthetic` feature.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::marathon_log::{Entry, EntryKind, EntryTiming, MarathonLog};
+use crate::utils::timekeeping::TWENTY_FOUR_HOURS_IN_SECS;
+
+/// How the generated runner plays.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyntheticConfig {
+    pub duration_secs: u32,
+    /// The chance of a guess being a 5k at the start of the marathon.
+    pub hit_rate: f64,
+    /// How much of the hit rate is lost by the end of the marathon, from 0 (none) to 1 (all).
+    pub fatigue: f64,
+    pub mean_round_secs: f64,
+    /// The standard deviation of round times.
+    pub round_secs_spread: f64,
+    /// The chance of a round timing out without a guess.
+    pub no_guess_rate: f64,
+    /// The chance of a game being skipped instead of played.
+    pub skip_rate: f64,
+    /// The chance of taking a break after a round.
+    pub break_rate: f64,
+    pub mean_break_secs: f64,
+}
+
+impl Default for SyntheticConfig {
+    fn default() -> Self {
+        SyntheticConfig {
+            duration_secs: TWENTY_FOUR_HOURS_IN_SECS,
+            hit_rate: 0.3,
+            fatigue: 0.3,
+            mean_round_secs: 25.0,
+            round_secs_spread: 10.0,
+            no_guess_rate: 0.01,
+            skip_rate: 0.005,
+            break_rate: 0.002,
+            mean_break_secs: 600.0,
+        }
+    }
+}
+
+impl SyntheticConfig {
+    /// A config with random but plausible settings.
+    pub fn random(rng: &mut impl Rng) -> Self {
+        SyntheticConfig {
+            duration_secs: rng.gen_range(600..=TWENTY_FOUR_HOURS_IN_SECS),
+            hit_rate: rng.gen_range(0.0..=1.0),
+            fatigue: rng.gen_range(0.0..=1.0),
+            mean_round_secs: rng.gen_range(2.0..120.0),
+            round_secs_spread: rng.gen_range(0.0..30.0),
+            no_guess_rate: rng.gen_range(0.0..0.1),
+            skip_rate: rng.gen_range(0.0..0.05),
+            break_rate: rng.gen_range(0.0..0.01),
+            mean_break_secs: rng.gen_range(0.0..3600.0),
+        }
+    }
+}
+
+/// Generates the entries of a whole marathon. The same config and seed always give the same log.
+pub fn generate_entries(config: &SyntheticConfig, seed: u64) -> Vec<Entry> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let duration = f64::from(config.duration_secs);
+    let mut entries = Vec::new();
+
+    let mut time = 0.0;
+    loop {
+        let round_secs = normal(&mut rng, config.mean_round_secs, config.round_secs_spread);
+        time += round_secs.max(1.0);
+        if time > duration {
+            break;
+        }
+
+        let (score, kind) = if rng.gen_bool(config.skip_rate) {
+            (0, EntryKind::Skipped)
+        } else if rng.gen_bool(config.no_guess_rate) {
+            (0, EntryKind::NoGuess)
+        } else {
+            let hit_rate = config.hit_rate * (1.0 - config.fatigue * time / duration);
+            (
+                guess_score(&mut rng, hit_rate.clamp(0.0, 1.0)),
+                EntryKind::Guess,
+            )
+        };
+        entries.push(Entry {
+            score,
+            time_secs: time as u32,
+            kind,
+            player: 0,
+            starts_game: false,
+            timing: EntryTiming::OnTime,
+        });
+
+        if rng.gen_bool(config.break_rate) {
+            time += rng.gen_range(0.0..=2.0 * config.mean_break_secs);
+        }
+    }
+
+    entries
+}
+
+/// Generates a whole marathon as a paused log.
+pub fn generate_log(config: &SyntheticConfig, seed: u64) -> MarathonLog {
+    MarathonLog::from_entries(config.duration_secs, generate_entries(config, seed))
+}
+
+/// A 5k with a chance of `hit_rate`, otherwise a miss that's usually close to a 5k.
+fn guess_score(rng: &mut impl Rng, hit_rate: f64) -> u16 {
+    if rng.gen_bool(hit_rate) {
+        return 5000;
+    }
+    let points_lost = -600.0 * rng.gen_range(f64::EPSILON..1.0).ln();
+    (5000.0 - points_lost.max(1.0)).clamp(0.0, 4999.0) as u16
+}
+
+/// A sample from a normal distribution, using the Box-Muller transform.
+fn normal(rng: &mut impl Rng, mean: f64, std_dev: f64) -> f64 {
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen_range(0.0..1.0);
+    let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+    mean + std_dev * z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUNS: u64 = 40;
+
+    fn random_run(seed: u64) -> (SyntheticConfig, MarathonLog) {
+        let config = SyntheticConfig::random(&mut StdRng::seed_from_u64(seed));
+        (config, generate_log(&config, seed))
+    }
+
+    #[test]
+    fn generated_entries_test() {
+        for seed in 0..RUNS {
+            let (config, marathon_log) = random_run(seed);
+            let mut last_time = 0;
+            for entry in marathon_log.entries() {
+                assert!(entry.time_secs >= last_time);
+                assert!(entry.time_secs <= config.duration_secs);
+                assert!(entry.score <= 5000);
+                if entry.kind != EntryKind::Guess {
+                    assert_eq!(entry.score, 0);
+                }
+                last_time = entry.time_secs;
+            }
+        }
+        let config = SyntheticConfig::default();
+        assert_eq!(generate_entries(&config, 7), generate_entries(&config, 7));
+    }
+
+    #[test]
+    fn counts_and_pace_test() {
+        for seed in 0..RUNS {
+            let (config, marathon_log) = random_run(seed);
+            let counted = marathon_log
+                .entries()
+                .filter(|entry| entry.score == 5000)
+                .count();
+            assert_eq!(marathon_log.count() as usize, counted);
+            assert_eq!(marathon_log.add_up_count(), marathon_log.count());
+
+            let mut rng = StdRng::seed_from_u64(seed);
+            for _ in 0..20 {
+                let time = rng.gen_range(0..=config.duration_secs);
+                let snapshot = marathon_log.snapshot_at(time);
+                if let Some(pace) = snapshot.estimated_pace {
+                    assert!(pace >= snapshot.count);
+                }
+                if let Some(since_5k) = snapshot.time_since_last_hit {
+                    assert!(since_5k <= time);
+                }
+            }
+            let end = marathon_log.snapshot_at(config.duration_secs);
+            assert_eq!(end.count, marathon_log.count());
+            if end.count > 0 {
+                assert_eq!(end.estimated_pace, Some(end.count));
+            }
+        }
+    }
+
+    #[test]
+    fn persistence_round_trip_test() {
+        for seed in 0..RUNS / 4 {
+            let (config, marathon_log) = random_run(seed);
+            let path = std::env::temp_dir().join(format!("geoguessr_tracker_synthetic_{seed}.ron"));
+            let path = path.to_str().unwrap();
+            let _ = std::fs::remove_file(path);
+            marathon_log.save_to_path(path).unwrap();
+
+            let mut loaded = MarathonLog::new(config.duration_secs);
+            let res = loaded.load_from_file(path);
+            std::fs::remove_file(path).unwrap();
+            res.unwrap();
+            assert!(loaded.entries().eq(marathon_log.entries()));
+            assert_eq!(loaded.count(), marathon_log.count());
+        }
+    }
+}
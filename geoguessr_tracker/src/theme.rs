@@ -0,0 +1,346 @@
+//! How the tracker window looks: colors, fonts, sizes and which panels it shows. Themes are RON
+//! files in the themes folder, so each runner or stream can have their own, and there are dark,
+//! light and high contrast presets to start from.
+
+use std::fmt;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::charts::Rgb;
+use crate::error::PersistError;
+
+/// The Okabe-Ito colors, which stay apart for the common kinds of color blindness.
+pub mod okabe_ito {
+    use crate::charts::Rgb;
+
+    pub const ORANGE: Rgb = Rgb(230, 159, 0);
+    pub const SKY_BLUE: Rgb = Rgb(86, 180, 233);
+    pub const BLUISH_GREEN: Rgb = Rgb(0, 158, 115);
+    pub const YELLOW: Rgb = Rgb(240, 228, 66);
+    pub const BLUE: Rgb = Rgb(0, 114, 178);
+    pub const VERMILLION: Rgb = Rgb(213, 94, 0);
+    pub const REDDISH_PURPLE: Rgb = Rgb(204, 121, 167);
+}
+
+/// Theme files are read from and saved to this folder in the tracker's folder.
+pub const THEMES_DIR: &str = "themes";
+/// How many theme files [`Theme::save_to_themes_dir`] tries before giving up.
+const THEME_FILE_COUNT: u32 = 100;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)] // so theme files only need the parts they change
+pub struct Theme {
+    /// Whether to start from egui's dark look rather than the light one.
+    pub dark: bool,
+    pub colours: ThemeColours,
+    pub fonts: ThemeFonts,
+    pub layout: ThemeLayout,
+    pub panels: ThemePanels,
+}
+
+/// Colors left as `None` keep egui's own for the dark or light look.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeColours {
+    pub background: Option<Rgb>,
+    pub text: Option<Rgb>,
+    /// Selections and links.
+    pub accent: Option<Rgb>,
+    /// Errors, warnings that need acting on, and droughts past the alarm.
+    pub error: Rgb,
+    /// Good news, and the last hit timer while it's short.
+    pub good: Rgb,
+    /// Droughts past the warning.
+    pub warning: Rgb,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeFonts {
+    /// A TTF or OTF file used instead of the built-in Recursive fonts.
+    pub font_file: Option<String>,
+    pub heading_size: f32,
+    /// The size of the numbers and body text.
+    pub body_size: f32,
+    pub button_size: f32,
+    pub small_size: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeLayout {
+    pub window_margin: f32,
+    pub item_spacing_x: f32,
+    pub item_spacing_y: f32,
+}
+
+/// Which parts of the tracker screen are shown. The timer, counts and paste box always are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemePanels {
+    pub alerts: bool,
+    pub longest_drought: bool,
+    pub players: bool,
+    pub games: bool,
+    pub splits: bool,
+    pub entry_buttons: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            dark: true,
+            colours: ThemeColours::default(),
+            fonts: ThemeFonts::default(),
+            layout: ThemeLayout::default(),
+            panels: ThemePanels::default(),
+        }
+    }
+}
+
+impl Default for ThemeColours {
+    fn default() -> Self {
+        ThemeColours {
+            background: None,
+            text: None,
+            accent: None,
+            error: Rgb(240, 10, 10),
+            good: Rgb(40, 170, 80),
+            warning: Rgb(220, 170, 0),
+        }
+    }
+}
+
+impl Default for ThemeFonts {
+    fn default() -> Self {
+        ThemeFonts {
+            font_file: None,
+            heading_size: 26.0,
+            body_size: 26.0,
+            button_size: 20.0,
+            small_size: 16.0,
+        }
+    }
+}
+
+impl Default for ThemeLayout {
+    fn default() -> Self {
+        ThemeLayout {
+            window_margin: 30.0,
+            item_spacing_x: 50.0,
+            item_spacing_y: 2.0,
+        }
+    }
+}
+
+impl Default for ThemePanels {
+    fn default() -> Self {
+        ThemePanels {
+            alerts: true,
+            longest_drought: true,
+            players: true,
+            games: true,
+            splits: true,
+            entry_buttons: true,
+        }
+    }
+}
+
+/// The themes built into the tracker.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemePreset {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+    /// Dark, with errors, warnings and good news told apart by the Okabe-Ito colors.
+    ColorblindDark,
+    ColorblindLight,
+}
+
+impl ThemePreset {
+    pub const ALL: [ThemePreset; 5] = [
+        ThemePreset::Dark,
+        ThemePreset::Light,
+        ThemePreset::HighContrast,
+        ThemePreset::ColorblindDark,
+        ThemePreset::ColorblindLight,
+    ];
+
+    pub fn theme(self) -> Theme {
+        match self {
+            ThemePreset::Dark => Theme::default(),
+            ThemePreset::Light => Theme {
+                dark: false,
+                colours: ThemeColours {
+                    error: Rgb(200, 0, 0),
+                    good: Rgb(0, 130, 50),
+                    warning: Rgb(180, 120, 0),
+                    ..ThemeColours::default()
+                },
+                ..Theme::default()
+            },
+            ThemePreset::HighContrast => Theme {
+                dark: true,
+                colours: ThemeColours {
+                    background: Some(Rgb(0, 0, 0)),
+                    text: Some(Rgb(255, 255, 255)),
+                    accent: Some(Rgb(0, 200, 255)),
+                    error: Rgb(255, 90, 90),
+                    good: Rgb(0, 255, 120),
+                    warning: Rgb(255, 230, 0),
+                },
+                fonts: ThemeFonts {
+                    heading_size: 28.0,
+                    body_size: 28.0,
+                    button_size: 22.0,
+                    small_size: 18.0,
+                    ..ThemeFonts::default()
+                },
+                ..Theme::default()
+            },
+            ThemePreset::ColorblindDark => Theme {
+                dark: true,
+                colours: ThemeColours {
+                    accent: Some(okabe_ito::SKY_BLUE),
+                    error: okabe_ito::VERMILLION,
+                    good: okabe_ito::SKY_BLUE,
+                    warning: okabe_ito::YELLOW,
+                    ..ThemeColours::default()
+                },
+                ..Theme::default()
+            },
+            ThemePreset::ColorblindLight => Theme {
+                dark: false,
+                colours: ThemeColours {
+                    accent: Some(okabe_ito::BLUE),
+                    error: okabe_ito::VERMILLION,
+                    good: okabe_ito::BLUE,
+                    warning: okabe_ito::ORANGE,
+                    ..ThemeColours::default()
+                },
+                ..Theme::default()
+            },
+        }
+    }
+}
+
+impl fmt::Display for ThemePreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ThemePreset::Dark => "Dark",
+            ThemePreset::Light => "Light",
+            ThemePreset::HighContrast => "High contrast",
+            ThemePreset::ColorblindDark => "Colorblind safe (dark)",
+            ThemePreset::ColorblindLight => "Colorblind safe (light)",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Where the theme in the settings comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeSource {
+    Preset(ThemePreset),
+    /// A theme file, by its path.
+    File(String),
+}
+
+impl Default for ThemeSource {
+    fn default() -> Self {
+        ThemeSource::Preset(ThemePreset::default())
+    }
+}
+
+impl fmt::Display for ThemeSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeSource::Preset(preset) => preset.fmt(f),
+            ThemeSource::File(path) => f.write_str(path),
+        }
+    }
+}
+
+impl Theme {
+    pub fn load(path: &str) -> Result<Self, PersistError> {
+        if !fs::exists(path).map_err(|err| PersistError::io(path, err))? {
+            return Err(PersistError::NotFound { path: path.into() });
+        }
+        let contents = fs::read_to_string(path).map_err(|err| PersistError::io(path, err))?;
+        ron::from_str(&contents).map_err(|err| PersistError::Deserialize {
+            path: path.into(),
+            line: err.position.line,
+            col: err.position.col,
+            source: err.code,
+        })
+    }
+
+    pub fn from_source(source: &ThemeSource) -> Result<Self, PersistError> {
+        match source {
+            ThemeSource::Preset(preset) => Ok(preset.theme()),
+            ThemeSource::File(path) => Theme::load(path),
+        }
+    }
+
+    /// Saves the theme to the first of themes/theme0.ron, themes/theme1.ron, ... that doesn't
+    /// exist yet, and returns its path. This is how a preset is turned into a file to edit.
+    pub fn save_to_themes_dir(&self) -> Result<String, PersistError> {
+        fs::create_dir_all(THEMES_DIR).map_err(|err| PersistError::io(THEMES_DIR, err))?;
+        let serialized = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(PersistError::Serialize)?;
+        for i in 0..THEME_FILE_COUNT {
+            let path = format!("{THEMES_DIR}/theme{i}.ron");
+            match fs::File::create_new(&path) {
+                Ok(_) => {
+                    fs::write(&path, &serialized).map_err(|err| PersistError::io(&path, err))?;
+                    return Ok(path);
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(PersistError::io(&path, err)),
+            }
+        }
+        Err(PersistError::AllFileNamesTaken {
+            first: format!("{THEMES_DIR}/theme0.ron").into(),
+            last: format!("{THEMES_DIR}/theme{}.ron", THEME_FILE_COUNT - 1).into(),
+        })
+    }
+}
+
+/// The theme files in the themes folder, sorted by path.
+pub fn theme_files() -> Vec<String> {
+    let Ok(dir) = fs::read_dir(THEMES_DIR) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<String> = dir
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_test() {
+        // a theme file only needs what it changes from the dark preset
+        let theme: Theme =
+            ron::from_str("(dark: false, colours: (error: (1, 2, 3)), panels: (splits: false))")
+                .unwrap();
+        assert!(!theme.dark);
+        assert_eq!(theme.colours.error, Rgb(1, 2, 3));
+        assert_eq!(theme.colours.good, ThemeColours::default().good);
+        assert!(!theme.panels.splits && theme.panels.games);
+
+        for preset in ThemePreset::ALL {
+            let theme = preset.theme();
+            let serialized = ron::to_string(&theme).unwrap();
+            assert_eq!(ron::from_str::<Theme>(&serialized).unwrap(), theme);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Turning a [`Theme`] into egui's style and fonts for the tracker window. The theme itself is
+//! plain data in the library, so it can be loaded and saved without egui.
+
+use std::fs;
+
+use eframe::egui::{
+    self, Color32, FontData, FontDefinitions, FontFamily, FontId, Margin, TextStyle, Vec2, Visuals,
+};
+use geoguessr_tracker::charts::Rgb;
+use geoguessr_tracker::error::PersistError;
+use geoguessr_tracker::theme::Theme;
+
+const MONO_FONT: &str = "Recursive Mono";
+const SANS_FONT: &str = "Recursive Sans";
+/// Where the font from the theme's font file goes in egui's font list.
+const THEME_FONT: &str = "Theme font";
+
+pub fn colour(rgb: Rgb) -> Color32 {
+    Color32::from_rgb(rgb.0, rgb.1, rgb.2)
+}
+
+/// The fonts for egui, with the font file of `theme` first if it has one. If the file can't be
+/// read the built-in fonts are used, and the error is returned with them.
+pub fn font_definitions(theme: &Theme) -> (FontDefinitions, Option<PersistError>) {
+    let mut font_defs = FontDefinitions::default();
+    font_defs.font_data.insert(
+        MONO_FONT.to_owned(),
+        FontData::from_static(include_bytes!("fonts/RecursiveMonoLnrSt-Bold.ttf")),
+    );
+    font_defs.font_data.insert(
+        SANS_FONT.to_owned(),
+        FontData::from_static(include_bytes!("fonts/RecursiveSansLnrSt-Bold.ttf")),
+    );
+    let mut mono_fonts = vec![MONO_FONT.to_owned()];
+    let mut sans_fonts = vec![SANS_FONT.to_owned()];
+
+    let mut font_err = None;
+    if let Some(path) = &theme.fonts.font_file {
+        match fs::read(path) {
+            Ok(bytes) => {
+                font_defs
+                    .font_data
+                    .insert(THEME_FONT.to_owned(), FontData::from_owned(bytes));
+                mono_fonts.insert(0, THEME_FONT.to_owned());
+                sans_fonts.insert(0, THEME_FONT.to_owned());
+            }
+            Err(err) => font_err = Some(PersistError::io(path, err)),
+        }
+    }
+    for (family, fonts) in [
+        (FontFamily::Monospace, mono_fonts),
+        (FontFamily::Proportional, sans_fonts),
+    ] {
+        let family_fonts = font_defs.families.entry(family).or_default();
+        for font in fonts.into_iter().rev() {
+            family_fonts.insert(0, font);
+        }
+    }
+    (font_defs, font_err)
+}
+
+/// The look of `theme`, on top of egui's default style.
+pub fn style(theme: &Theme) -> egui::Style {
+    let mut style = egui::Style {
+        visuals: match theme.dark {
+            true => Visuals::dark(),
+            false => Visuals::light(),
+        },
+        ..egui::Style::default()
+    };
+
+    let colours = &theme.colours;
+    let visuals = &mut style.visuals;
+    if let Some(background) = colours.background {
+        visuals.panel_fill = colour(background);
+        visuals.window_fill = colour(background);
+    }
+    visuals.override_text_color = colours.text.map(colour);
+    if let Some(accent) = colours.accent {
+        visuals.selection.bg_fill = colour(accent);
+        visuals.hyperlink_color = colour(accent);
+    }
+    visuals.error_fg_color = colour(colours.error);
+    visuals.warn_fg_color = colour(colours.warning);
+
+    let layout = &theme.layout;
+    style.spacing.window_margin = Margin::same(layout.window_margin);
+    style.spacing.item_spacing = Vec2 {
+        x: layout.item_spacing_x,
+        y: layout.item_spacing_y,
+    };
+
+    let fonts = &theme.fonts;
+    style.text_styles = [
+        (
+            TextStyle::Heading,
+            FontId::new(fonts.heading_size, FontFamily::Proportional),
+        ),
+        (
+            TextStyle::Body,
+            FontId::new(fonts.body_size, FontFamily::Monospace),
+        ),
+        (
+            TextStyle::Monospace,
+            FontId::new(fonts.body_size, FontFamily::Monospace),
+        ),
+        (
+            TextStyle::Button,
+            FontId::new(fonts.button_size, FontFamily::Proportional),
+        ),
+        (
+            TextStyle::Small,
+            FontId::new(fonts.small_size, FontFamily::Proportional),
+        ),
+    ]
+    .into();
+    style
+}
+
+/// Sets the style and fonts of `theme` on `ctx`. Returns the error if the font file couldn't be
+/// read, in which case the built-in fonts are used.
+pub fn apply_theme(theme: &Theme, ctx: &egui::Context) -> Result<(), PersistError> {
+    ctx.set_style(style(theme));
+    let (font_defs, font_err) = font_definitions(theme);
+    ctx.set_fonts(font_defs);
+    font_err.map_or(Ok(()), Err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geoguessr_tracker::theme::{ThemeFonts, ThemePreset};
+
+    #[test]
+    fn theme_style_test() {
+        let style = style(&ThemePreset::HighContrast.theme());
+        assert_eq!(style.visuals.panel_fill, Color32::BLACK);
+        assert_eq!(style.text_styles[&TextStyle::Small].size, 18.0);
+
+        let missing_font = Theme {
+            fonts: ThemeFonts {
+                font_file: Some("no_such_font.ttf".to_owned()),
+                ..ThemeFonts::default()
+            },
+            ..Theme::default()
+        };
+        let (font_defs, font_err) = font_definitions(&missing_font);
+        assert!(font_err.is_some());
+        assert_eq!(font_defs.families[&FontFamily::Monospace][0], MONO_FONT);
+    }
+}
@@ -1,17 +1,16 @@
-use std::str::FromStr;
-
-use log::debug;
-
+use crate::i18n::{tr, trf};
 use timekeeping::*;
 
-pub(crate) fn calculate_countdown(time_since_epoch: u32, epoch_offset_secs: u32) -> String {
+/// Formats the time left in a 24 hour marathon as "HH:MM:SS".
+pub fn calculate_countdown(time_since_epoch: u32, epoch_offset_secs: u32) -> String {
     let time_passed = time_since_epoch + epoch_offset_secs;
     let time_remaining = TWENTY_FOUR_HOURS_IN_SECS.saturating_sub(time_passed);
 
     time_counter(time_remaining)
 }
 
-pub(crate) fn time_counter(raw_secs: u32) -> String {
+/// Formats a number of seconds as "HH:MM:SS".
+pub fn time_counter(raw_secs: u32) -> String {
     let time_hms = HoursMinutesSeconds::from_secs(raw_secs);
     let mut h = time_hms.hours.to_string();
     let mut m = time_hms.minutes.to_string();
@@ -25,27 +24,25 @@ pub(crate) fn time_counter(raw_secs: u32) -> String {
     h + ":" + &m + ":" + &s
 }
 
-pub(crate) fn score_from_str(input_str: &str) -> Result<u16, String> {
-    if input_str.lines().count() < 1 {
-        return Err("no lines in input".to_owned());
-    }
-    let first_line = input_str.lines().next().unwrap(); // string is know to have at least one line
-    let mut score_str = String::new();
-    for ch in first_line.chars() {
-        if ch.is_ascii_digit() {
-            score_str.push(ch);
-            continue;
-        } else if ch.is_whitespace() || ch == ',' {
-            continue;
-        } else {
-            return Err(input_str.to_owned());
-        }
-    }
-    debug!("score str: {}", &score_str);
-
-    match u16::from_str(&score_str) {
-        Ok(score) => Ok(score),
-        Err(e) => Err(e.to_string()),
+/// Says a number of seconds in words, e.g. "1 hour 5 minutes", for screen readers.
+pub fn spoken_duration(raw_secs: u32) -> String {
+    let time_hms = HoursMinutesSeconds::from_secs(raw_secs);
+    let parts = [
+        (u32::from(time_hms.hours), "1 hour", "{0} hours"),
+        (u32::from(time_hms.minutes), "1 minute", "{0} minutes"),
+        (u32::from(time_hms.seconds), "1 second", "{0} seconds"),
+    ];
+    let words: Vec<String> = parts
+        .iter()
+        .filter(|(n, _, _)| *n > 0)
+        .map(|(n, one, many)| match n {
+            1 => tr(one),
+            _ => trf(many, &[n]),
+        })
+        .collect();
+    match words.is_empty() {
+        true => trf("{0} seconds", &[&0]),
+        false => words.join(" "),
     }
 }
 
@@ -75,9 +72,9 @@ pub mod timekeeping {
         }
 
         pub fn from_strs(hours: &str, mins: &str, secs: &str) -> Result<Self, ParseIntError> {
-            let hours_int = u8::from_str(&hours)?;
-            let mins_int = u8::from_str(&mins)?;
-            let secs_int = u8::from_str(&secs)?;
+            let hours_int = u8::from_str(hours)?;
+            let mins_int = u8::from_str(mins)?;
+            let secs_int = u8::from_str(secs)?;
             Ok(HoursMinutesSeconds {
                 hours: hours_int,
                 minutes: mins_int,
@@ -89,6 +86,19 @@ pub mod timekeeping {
             ((self.hours as u32) * 60 * 60) + ((self.minutes as u32) * 60) + (self.seconds as u32)
         }
     }
+
+    /// Parses `HH:MM:SS`, as shown by the tracker.
+    impl FromStr for HoursMinutesSeconds {
+        type Err = ParseIntError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let mut parts = s.trim().splitn(3, ':');
+            let hours = parts.next().unwrap_or("");
+            let mins = parts.next().unwrap_or("");
+            let secs = parts.next().unwrap_or("");
+            HoursMinutesSeconds::from_strs(hours, mins, secs)
+        }
+    }
 }
 
 // TODO: add more tests
@@ -124,4 +134,11 @@ mod tests {
         };
         assert_eq!(correct, HoursMinutesSeconds::from_secs(total_seconds));
     }
+
+    #[test]
+    fn spoken_duration_test() {
+        assert_eq!(spoken_duration(0), "0 seconds");
+        assert_eq!(spoken_duration(3600 + 5 * 60), "1 hour 5 minutes");
+        assert_eq!(spoken_duration(2 * 3600 + 1), "2 hours 1 second");
+    }
 }
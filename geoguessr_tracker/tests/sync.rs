@@ -0,0 +1,144 @@
+//! Runs a sync host and several clients on this machine.
+
+use std::net::TcpListener;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use geoguessr_tracker::marathon_log::{Entry, EntryKind, EntryTiming, MarathonLog};
+use geoguessr_tracker::sync::{SyncClient, SyncHost};
+
+fn entry(score: u16, time_secs: u32) -> Entry {
+    Entry {
+        score,
+        time_secs,
+        kind: EntryKind::Guess,
+        player: 0,
+        starts_game: false,
+        timing: EntryTiming::OnTime,
+    }
+}
+
+/// Polls the host until the log has `count` entries, or panics after a few seconds.
+fn wait_for_entries(host: &mut SyncHost, marathon_log: &mut MarathonLog, count: usize) {
+    let start = Instant::now();
+    while marathon_log.entries().count() < count {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "entries didn't sync in time"
+        );
+        host.poll(marathon_log);
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn clients_sync_to_host_test() {
+    // find a free port, so a client can start trying to connect before the host exists
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let addr = format!("127.0.0.1:{port}");
+
+    let mut early = SyncClient::connect(addr.clone(), "Early");
+    early.send(entry(5000, 10));
+    thread::sleep(Duration::from_millis(300));
+    assert!(!early.is_connected());
+
+    let mut host = SyncHost::start(&addr).unwrap();
+    let mut marathon_log = MarathonLog::new(1000);
+    marathon_log.set_player_names(vec!["Host".to_owned()]);
+    marathon_log.resume();
+    marathon_log.try_add_entry(5000);
+
+    let mut late = SyncClient::connect(addr, "Late");
+    late.send(entry(5000, 5));
+    late.send(entry(2000, 20));
+    early.send(entry(4000, 30));
+    wait_for_entries(&mut host, &mut marathon_log, 5);
+    assert!(early.is_connected() && late.is_connected());
+    assert_eq!(host.connected_players(), vec!["Early", "Late"]);
+
+    let times: Vec<u32> = marathon_log
+        .entries()
+        .map(|entry| entry.time_secs)
+        .collect();
+    assert_eq!(times, vec![0, 5, 10, 20, 30]);
+    assert_eq!(marathon_log.count(), 3);
+    let stats = marathon_log.player_stats();
+    let mut totals: Vec<(&str, u32, u32)> = stats
+        .iter()
+        .map(|stats| (stats.name.as_str(), stats.count, stats.rounds))
+        .collect();
+    // clients are added as players in whichever order their entries arrive
+    totals.sort();
+    assert_eq!(
+        totals,
+        vec![("Early", 1, 2), ("Host", 1, 1), ("Late", 1, 2)]
+    );
+
+    drop(late);
+    thread::sleep(Duration::from_millis(300));
+    host.poll(&mut marathon_log);
+    assert_eq!(host.connected_players(), vec!["Early"]);
+}
+
+#[test]
+fn reconnected_client_syncs_test() {
+    let mut host = SyncHost::start("127.0.0.1:0").unwrap();
+    let addr = host.local_addr().to_string();
+    let mut marathon_log = MarathonLog::new(1000);
+    marathon_log.resume();
+
+    let mut first = SyncClient::connect(addr.clone(), "Bea");
+    first.send(entry(5000, 10));
+    first.send(entry(3000, 20));
+    wait_for_entries(&mut host, &mut marathon_log, 2);
+    drop(first);
+
+    // a restarted tracker, or a second one with the same name, numbers its entries from 0 again
+    let mut restarted = SyncClient::connect(addr.clone(), "Bea");
+    restarted.send(entry(5000, 30));
+    let mut same_name = SyncClient::connect(addr, "Bea");
+    same_name.send(entry(4000, 40));
+    wait_for_entries(&mut host, &mut marathon_log, 4);
+
+    let times: Vec<u32> = marathon_log
+        .entries()
+        .map(|entry| entry.time_secs)
+        .collect();
+    assert_eq!(times, vec![10, 20, 30, 40]);
+    assert_eq!(marathon_log.count(), 2);
+}
+
+#[test]
+fn paused_host_turns_entries_away_test() {
+    let mut host = SyncHost::start("127.0.0.1:0").unwrap();
+    let addr = host.local_addr().to_string();
+    // a new log is paused, and turns entries away by default
+    let mut marathon_log = MarathonLog::new(1000);
+
+    let mut client = SyncClient::connect(addr, "Bea");
+    client.send(entry(5000, 10));
+    let start = Instant::now();
+    while client.rejected_count() == 0 {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "the client wasn't told its entry was turned away"
+        );
+        host.poll(&mut marathon_log);
+        thread::sleep(Duration::from_millis(20));
+    }
+    assert_eq!(marathon_log.entries().count(), 0);
+
+    // the client still sends entries once the host takes them again
+    marathon_log.resume();
+    client.send(entry(4000, 20));
+    wait_for_entries(&mut host, &mut marathon_log, 1);
+    assert_eq!(client.rejected_count(), 1);
+    assert_eq!(
+        marathon_log.last_entry().map(|entry| entry.score),
+        Some(4000)
+    );
+}